@@ -12,14 +12,31 @@
 use core::f32;
 use core::f32::consts::PI;
 
+use bevy::input::common_conditions::input_just_released;
 use bevy::input::common_conditions::input_pressed;
+use bevy::pbr::wireframe::WireframePlugin;
+use bevy::picking::backend::ray::RayMap;
 use bevy::prelude::Cone;
 use bevy::{color::palettes::tailwind::*, picking::pointer::PointerInteraction, prelude::*};
 use bevy_hopf::HopfPlugin;
-use bevy_hopf::hopf::HopfMeshBuilder;
+use bevy_hopf::camera::BlocksCameraDrag;
+use bevy_hopf::camera::HopfOrbitCamera;
+use bevy_hopf::camera::HopfSelected;
+use bevy_hopf::export::ExportHopfScene;
+use bevy_hopf::fibre::HopfFibre;
+use bevy_hopf::flow::HopfFlow;
+use bevy_hopf::flow::HopfFlowPlugin;
+use bevy_hopf::hopf::HopfSurface;
+use bevy_hopf::hopf::SeedDistribution;
+use bevy_hopf::panel::HopfPanelPlugin;
+use bevy_hopf::panel::spawn_hopf_panel;
+use bevy_hopf::pick::fibre_point_on_triangle;
 use bevy_mod_mesh_tools::mesh_with_transform;
 use bevy_picking::Pickable;
 
+use hopf::F32_4PI;
+use hopf::fibre::Fibre;
+use hopf::rotation::rotation_from_angles;
 use hopf::sp::SurfacePoint;
 
 static Y_HEIGHT: f32 = 3.0_f32;
@@ -27,13 +44,39 @@ static Y_HEIGHT: f32 = 3.0_f32;
 fn main() {
     App::new()
         // MeshPickingPlugin is not a default plugin
-        .add_plugins((DefaultPlugins, HopfPlugin, MeshPickingPlugin))
+        .add_plugins((
+            DefaultPlugins,
+            HopfPlugin { orbit_camera: true },
+            HopfFlowPlugin,
+            HopfPanelPlugin,
+            MeshPickingPlugin,
+            WireframePlugin::default(),
+        ))
         .add_systems(Startup, setup_scene)
-        .add_systems(Update, (draw_mesh_intersections, rotate, mouse_down_system))
+        .init_resource::<DragState>()
+        .init_resource::<Selection>()
+        .init_resource::<S3Angles>()
+        .add_systems(
+            Update,
+            (
+                draw_mesh_intersections,
+                draw_base_line,
+                highlight_selected_fibre,
+                sync_camera_selection,
+                delete_selected_fibre,
+                pick_fibre,
+                rotate_s3,
+                toggle_flow,
+                export_scene,
+            ),
+        )
         // .add_systems(Update, draw_cursor)
         .add_systems(
             Update,
-            mouse_down_system.run_if(input_pressed(MouseButton::Left)),
+            (
+                drag_handle.run_if(input_pressed(MouseButton::Left)),
+                release_handle.run_if(input_just_released(MouseButton::Left)),
+            ),
         )
         .add_systems(Update, close_on_esc)
         .run();
@@ -43,12 +86,58 @@ fn main() {
 #[derive(Component)]
 struct Shape;
 
+/// Which end of the base line a handle controls.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum LineEnd {
+    Start,
+    End,
+}
+
 #[derive(Component)]
-struct IndicatorHandle;
+struct IndicatorHandle {
+    end: LineEnd,
+    /// Current position on the indicator sphere.
+    sp: SurfacePoint,
+}
+
+/// The handle being dragged, if any.
+#[derive(Default, Resource)]
+struct DragState {
+    handle: Option<Entity>,
+}
 
 #[derive(Component)]
 struct IndicatorBall;
 
+/// A fibre spawned by clicking on the indicator sphere.
+#[derive(Component)]
+struct SpawnedFibre {
+    /// The marker, on the indicator sphere, of the fibre's base point.
+    marker: Entity,
+}
+
+/// Marks the base point of a spawned fibre.
+#[derive(Component)]
+struct FibreMarker {
+    fibre: Entity,
+}
+
+/// Shared mesh for every [`FibreMarker`].
+#[derive(Resource)]
+struct FibreMarkerMesh(Handle<Mesh>);
+
+/// On screen text describing the fibre under the pointer.
+#[derive(Component)]
+struct HoverReadout;
+
+/// Rotation of S3 in each plane, in [`hopf::rotation::Plane::ALL`] order.
+#[derive(Default, Resource)]
+struct S3Angles([f32; 6]);
+
+/// The spawned fibre, or the Hopf surface, which is currently selected.
+#[derive(Default, Resource)]
+struct Selection(Option<Entity>);
+
 #[derive(Component)]
 struct Ground;
 
@@ -59,6 +148,15 @@ struct Hopf;
 const SHAPES_X_EXTENT: f32 = 8.0;
 const Z_EXTENT: f32 = 10.0;
 
+const INDICATOR_BALL_RADIUS: f32 = 2.0;
+
+// Keep the handles off the poles, the north pole cannot be projected.
+// Close to it the fibres grow huge, and f32 precision limits their sampling.
+const LAT_LIMIT: f32 = 80_f32.to_radians();
+
+// Presses further than this from every handle spawn a fibre instead.
+const HANDLE_PICK_ANGLE: f32 = 15_f32.to_radians();
+
 fn setup_scene(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
@@ -86,19 +184,18 @@ fn setup_scene(
     let hopf_pressed_matl = materials.add(hopf_pressed_matl);
 
     // Gizmo
-    let indicator_ball_radius = 2.0;
+    let indicator_ball_radius = INDICATOR_BALL_RADIUS;
     let indicator_height = 0.5;
     let indicator_radius = 0.25; // as aspect ration of 0.5;
 
-    // North pole
     let line_start = SurfacePoint {
-        lat: 0_f32.to_radians(),
+        lat: 10_f32.to_radians(),
         lon: 0_f32,
     };
 
     let line_end = SurfacePoint {
-        lat: 0_f32.to_radians(),
-        lon: 0_f32.to_radians(),
+        lat: 10_f32.to_radians(),
+        lon: 270_f32.to_radians(),
     };
 
     // The Entity transform will be used SOLEY to set the lat /lon.
@@ -138,41 +235,31 @@ fn setup_scene(
             // Attach an observer to handle drag events
             Shape,
             IndicatorBall,
+            // Dragging the handles must not orbit the camera.
+            BlocksCameraDrag,
         ))
         // Children position determined in relation to the parent transform.
         .with_children(|parent| {
-            // Start Indicator ( lat, lon )
-            parent.spawn((
-                IndicatorHandle,
-                Mesh3d(indicator.clone()),
-                MeshMaterial3d(indicator_mtl.clone()),
-                Transform::default(),
-                // Transform::from_rotation(Quat::from_euler(
-                //     EulerRot::XYZEx,
-                //     0_f32,
-                //     line_start.lon,
-                //     line_start.lat,
-                // )),
-            ));
-
-            // End Indicator is ( lat, lon )
-            // parent.spawn((
-            //     Mesh3d(indicator),
-            //     MeshMaterial3d(indicator_mtl),
-            //     Transform::from_rotation(Quat::from_euler(
-            //         EulerRot::XYZEx,
-            //         0_f32,
-            //         line_end.lon,
-            //         line_end.lat,
-            //     )),
-            //     IndicatorHandle,
-            // ));
+            // Start and end indicators ( lat, lon )
+            for (end, sp) in [(LineEnd::Start, line_start), (LineEnd::End, line_end)] {
+                parent.spawn((
+                    IndicatorHandle { end, sp },
+                    Mesh3d(indicator.clone()),
+                    MeshMaterial3d(indicator_mtl.clone()),
+                    Transform::from_rotation(handle_rotation(sp)),
+                    // Let the pointer fall through onto the sphere.
+                    Pickable::IGNORE,
+                ));
+            }
         })
         .observe(update_material_on::<Pointer<Over>>(hover_matl.clone()))
         .observe(update_material_on::<Pointer<Out>>(white_matl))
         .observe(update_material_on::<Pointer<Press>>(pressed_matl.clone()))
         .observe(update_material_on::<Pointer<Release>>(hover_matl.clone()))
-        .observe(rotate_on_drag);
+        .observe(select_handle)
+        .observe(spawn_fibre);
+
+    commands.insert_resource(FibreMarkerMesh(meshes.add(Sphere::new(0.08))));
 
     let compass_origin = Vec3::new(
         -SHAPES_X_EXTENT / 2. + i as f32 / (2 - 1) as f32 * SHAPES_X_EXTENT,
@@ -186,29 +273,21 @@ fn setup_scene(
         )))
         .insert(Transform::from_translation(compass_origin));
 
-    let line_start = SurfacePoint {
-        lat: 10_f32.to_radians(),
-        lon: 0_f32,
-    };
-
-    let line_end = SurfacePoint {
-        lat: 10_f32.to_radians(),
-        lon: 270_f32.to_radians(),
-    };
-
     // Hopf Object
-    let hopf_builder = HopfMeshBuilder::new(&line_start, &line_end, 27, 2000);
-
-    let hopf_mesh = hopf_builder
-        .construct::<40>()
-        .expect("Failed to construct mesh")
-        .build();
+    // HopfPlugin builds the Mesh3d, and rebuilds it when the surface changes.
+    let hopf_surface = HopfSurface {
+        line_start,
+        line_end,
+        n_loops: 27,
+        n_points_per_loop: 40,
+        ..default()
+    };
 
     // Hopf mesh
     let i = 1;
-    commands
+    let hopf_entity = commands
         .spawn((
-            Mesh3d(meshes.add(hopf_mesh)),
+            hopf_surface,
             MeshMaterial3d(hopf_white_matl.clone()),
             Transform::from_xyz(
                 -SHAPES_X_EXTENT / 2. + i as f32 / (2 - 1) as f32 * SHAPES_X_EXTENT,
@@ -228,7 +307,9 @@ fn setup_scene(
         .observe(update_material_on::<Pointer<Release>>(
             hopf_hover_matl.clone(),
         ))
-        .observe(rotate_on_drag);
+        .observe(select_surface)
+        .id();
+    spawn_hopf_panel(&mut commands, hopf_entity);
 
     // Ground
     commands.spawn((
@@ -251,14 +332,31 @@ fn setup_scene(
     ));
 
     // Camera
+    // HopfPlugin drives the transform.
     commands.spawn((
         Camera3d::default(),
-        Transform::from_xyz(0.0, 7., 14.0).looking_at(Vec3::new(0., 1., 0.), Vec3::Y),
+        // F rotates S3, see rotate_s3.
+        HopfOrbitCamera {
+            frame_key: KeyCode::KeyZ,
+            ..HopfOrbitCamera::looking_at(Vec3::new(0., 7., 14.0), Vec3::new(0., 1., 0.))
+        },
     ));
 
     // Instructions
     commands.spawn((
-        Text::new("Hover over the shapes to pick them\nDrag to rotate"),
+        Text::new(
+            "Hover over the shapes to pick them\n\
+             Drag the handles to edit the base line\n\
+             Click the sphere to add a fibre, click its marker to select it\n\
+             Delete removes the selected fibre\n\
+             Drag elsewhere to orbit, scroll to zoom, Z frames the selection\n\
+             R/F T/G Y/H rotate S3 in the XY, XZ, YZ planes\n\
+             Q/A W/S E/D rotate S3 through infinity, in the XW, YW, ZW planes\n\
+             Space resets the rotation\n\
+             M toggles the flow along the fibres\n\
+             The panel, top right, edits the surface\n\
+             X exports the surfaces and fibres to hopf_export.obj, Shift+X to hopf_export.ply",
+        ),
         Node {
             position_type: PositionType::Absolute,
             top: px(12),
@@ -266,6 +364,17 @@ fn setup_scene(
             ..default()
         },
     ));
+
+    commands.spawn((
+        HoverReadout,
+        Text::default(),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: px(12),
+            left: px(12),
+            ..default()
+        },
+    ));
 }
 
 /// Returns an observer that updates the entity's material to the one specified.
@@ -294,50 +403,411 @@ fn draw_mesh_intersections(pointers: Query<&PointerInteraction>, mut gizmos: Giz
     }
 }
 
-/// A system that rotates all shapes.
-fn rotate(mut _query: Query<&mut Transform, With<Shape>>, _time: Res<Time>) {
-    // for mut transform in &mut query {
-    //     transform.rotate_y(time.delta_secs() / 2.);
-    // }
+/// An observer selecting the Hopf surface when it is clicked.
+#[allow(clippy::needless_pass_by_value)]
+fn select_surface(click: On<Pointer<Click>>, mut selected: ResMut<Selection>) {
+    selected.0 = Some(click.event_target());
+}
+
+/// A system moving [`HopfSelected`], which the camera frames, onto the selection.
+#[allow(clippy::needless_pass_by_value)]
+fn sync_camera_selection(
+    mut commands: Commands,
+    selected: Res<Selection>,
+    previous: Query<Entity, With<HopfSelected>>,
+) {
+    if !selected.is_changed() {
+        return;
+    }
+    for entity in &previous {
+        commands.entity(entity).remove::<HopfSelected>();
+    }
+    if let Some(entity) = selected.0 {
+        commands.entity(entity).insert(HopfSelected);
+    }
 }
 
-/// An observer to rotate an entity when it is dragged
-fn rotate_on_drag(_drag: On<Pointer<Drag>>, _transforms: Query<&mut Transform>) {
-    // let mut transform = transforms.get_mut(drag.entity).unwrap();
-    // transform.rotate_y(drag.delta.x * 0.02);
-    // transform.rotate_x(drag.delta.y * 0.02);
+/// Rotation taking the indicator, modelled at (lat 0, lon 0), to the point on the sphere.
+fn handle_rotation(sp: SurfacePoint) -> Quat {
+    Quat::from_rotation_arc(Vec3::NEG_Z, sp.to_cartesian(1_f32))
 }
 
-fn mouse_down_system(
-    mouse_button_input: Res<ButtonInput<MouseButton>>,
+/// Converts a world space hit on the indicator sphere into a point on the sphere.
+fn to_surface_point(ball: &GlobalTransform, position: Vec3) -> SurfacePoint {
+    let local = ball.affine().inverse().transform_point3(position);
+    let mut sp = SurfacePoint::from_cartesian(local);
+    sp.lat = sp.lat.clamp(-LAT_LIMIT, LAT_LIMIT);
+    sp
+}
+
+/// An observer selecting the handle closest to where the sphere was pressed.
+#[allow(clippy::needless_pass_by_value)]
+fn select_handle(
+    press: On<Pointer<Press>>,
+    ball: Single<&GlobalTransform, With<IndicatorBall>>,
+    handles: Query<(Entity, &IndicatorHandle)>,
+    mut drag: ResMut<DragState>,
+) {
+    let Some(position) = press.hit.position else {
+        return;
+    };
+    let sp = to_surface_point(&ball, position);
+
+    drag.handle = nearest_handle(&handles, sp)
+        .filter(|(_, angle)| *angle < HANDLE_PICK_ANGLE)
+        .map(|(entity, _)| entity);
+}
+
+/// The handle closest to `sp`, and the angle between them.
+fn nearest_handle(
+    handles: &Query<(Entity, &IndicatorHandle)>,
+    sp: SurfacePoint,
+) -> Option<(Entity, f32)> {
+    let direction = sp.to_cartesian(1_f32);
+    handles
+        .iter()
+        .map(|(entity, handle)| {
+            let angle = handle.sp.to_cartesian(1_f32).angle_between(direction);
+            (entity, angle)
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+}
+
+/// Colour of a fibre, hue follows the longitude and lightness the latitude.
+fn fibre_color(sp: SurfacePoint) -> Color {
+    let lightness = 0.15_f32.mul_add(sp.lat.sin(), 0.5);
+    Color::hsl(sp.lon.to_degrees(), 0.9, lightness)
+}
+
+/// An observer spawning the fibre of the clicked point, away from the handles.
+///
+/// The fibre shares the transform of the Hopf mesh, and a marker of
+/// the same colour is placed on the sphere.
+#[allow(clippy::needless_pass_by_value)]
+fn spawn_fibre(
+    click: On<Pointer<Click>>,
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    marker_mesh: Res<FibreMarkerMesh>,
+    ball: Single<(Entity, &GlobalTransform), With<IndicatorBall>>,
+    surface: Single<(Entity, &HopfSurface), With<Hopf>>,
+    handles: Query<(Entity, &IndicatorHandle)>,
+) {
+    if click.button != PointerButton::Primary {
+        return;
+    }
+    let Some(position) = click.hit.position else {
+        return;
+    };
+    let (ball_entity, ball_transform) = *ball;
+    let sp = to_surface_point(ball_transform, position);
+    // Pressing near a handle drags it instead.
+    if nearest_handle(&handles, sp).is_some_and(|(_, angle)| angle < HANDLE_PICK_ANGLE) {
+        return;
+    }
+
+    let color = fibre_color(sp);
+    let material = materials.add(StandardMaterial {
+        base_color: color,
+        unlit: true,
+        ..default()
+    });
+
+    let fibre = commands
+        .spawn((
+            // HopfPlugin builds the Mesh3d.
+            HopfFibre {
+                sp,
+                rotation: surface.1.rotation,
+                ..default()
+            },
+            MeshMaterial3d(material.clone()),
+            ChildOf(surface.0),
+        ))
+        .id();
+    let marker = commands
+        .spawn((
+            FibreMarker { fibre },
+            Mesh3d(marker_mesh.0.clone()),
+            MeshMaterial3d(material),
+            Transform::from_translation(sp.to_cartesian(INDICATOR_BALL_RADIUS)),
+            ChildOf(ball_entity),
+        ))
+        .observe(select_fibre)
+        .id();
+    commands.entity(fibre).insert(SpawnedFibre { marker });
+}
+
+/// An observer selecting the fibre whose marker was clicked.
+fn select_fibre(
+    mut click: On<Pointer<Click>>,
+    markers: Query<&FibreMarker>,
+    mut selected: ResMut<Selection>,
+) {
+    // Do not bubble up to the sphere, which would spawn another fibre.
+    click.propagate(false);
+    if let Ok(marker) = markers.get(click.event_target()) {
+        selected.0 = Some(marker.fibre);
+    }
+}
+
+/// A system enlarging the marker of the selected fibre.
+#[allow(clippy::needless_pass_by_value)]
+fn highlight_selected_fibre(
+    selected: Res<Selection>,
+    mut markers: Query<(&FibreMarker, &mut Transform)>,
+) {
+    if !selected.is_changed() {
+        return;
+    }
+    for (marker, mut transform) in &mut markers {
+        let scale = if selected.0 == Some(marker.fibre) {
+            2_f32
+        } else {
+            1_f32
+        };
+        transform.scale = Vec3::splat(scale);
+    }
+}
+
+/// A system despawning the selected fibre, and its marker, when delete is pressed.
+#[allow(clippy::needless_pass_by_value)]
+fn delete_selected_fibre(
+    mut commands: Commands,
+    input: Res<ButtonInput<KeyCode>>,
+    mut selected: ResMut<Selection>,
+    fibres: Query<&SpawnedFibre>,
+) {
+    if !input.any_just_pressed([KeyCode::Delete, KeyCode::Backspace]) {
+        return;
+    }
+    // Only spawned fibres can be deleted, not the surface.
+    let Some((entity, fibre)) = selected.0.and_then(|e| Some((e, fibres.get(e).ok()?))) else {
+        return;
+    };
+    selected.0 = None;
+    commands.entity(fibre.marker).despawn();
+    commands.entity(entity).despawn();
+}
+
+/// While the mouse is held, moves the selected handle to the hit point
+/// and updates the matching end of the Hopf surface's base line.
+#[allow(clippy::needless_pass_by_value)]
+fn drag_handle(
+    drag: Res<DragState>,
     pointers: Query<&PointerInteraction>,
-    indicator_ball: Single<&Children, With<IndicatorBall>>,
-    mut handles: Query<(&IndicatorHandle, &mut Transform)>,
+    ball: Single<(Entity, &GlobalTransform), With<IndicatorBall>>,
+    mut handles: Query<(&mut IndicatorHandle, &mut Transform)>,
+    mut surface: Single<&mut HopfSurface, With<Hopf>>,
 ) {
-    // Is this needed as the system is wrapped in a  runs_if()
-    // Could reverse the nested for loops here
-    // and search handles first.
-    if mouse_button_input.pressed(MouseButton::Left) {
-        for normal in pointers
-            .iter()
-            .filter_map(|interaction| interaction.get_nearest_hit())
-            .filter_map(|(_entity, hit)| hit.normal)
-        {
-            // There is only one hit here.
-
-            // TODO: Select handle based on the proximity to hit point.
-            for handle_id in indicator_ball.iter() {
-                if let Ok((_indicator_handle, mut transform)) = handles.get_mut(handle_id) {
-                    let from = transform.forward().into();
-                    println!("surface normal {normal}");
-                    println!("forward {}", from);
-
-                    let quat = Quat::from_rotation_arc(from, normal);
-                    transform.rotate(quat);
-                    println!("transform (after) {}", transform.forward());
-                    println!();
-                }
-            }
+    let Some(handle) = drag.handle else {
+        return;
+    };
+    let Ok((mut indicator, mut transform)) = handles.get_mut(handle) else {
+        return;
+    };
+    let (ball_entity, ball_transform) = *ball;
+
+    // Only hits on the sphere itself move the handle.
+    for position in pointers
+        .iter()
+        .filter_map(|interaction| interaction.get_nearest_hit())
+        .filter(|(entity, _hit)| *entity == ball_entity)
+        .filter_map(|(_entity, hit)| hit.position)
+    {
+        let sp = to_surface_point(ball_transform, position);
+        if sp == indicator.sp {
+            continue;
+        }
+        indicator.sp = sp;
+        transform.rotation = handle_rotation(sp);
+
+        // HopfPlugin rebuilds the mesh, when the surface changes.
+        match indicator.end {
+            LineEnd::Start => surface.line_start = sp,
+            LineEnd::End => surface.line_end = sp,
+        }
+    }
+}
+
+fn release_handle(mut drag: ResMut<DragState>) {
+    drag.handle = None;
+}
+
+/// A system that draws the base line, between the two handles, onto the indicator sphere.
+#[allow(clippy::needless_pass_by_value)]
+fn draw_base_line(
+    ball: Single<&GlobalTransform, With<IndicatorBall>>,
+    surface: Single<&HopfSurface, With<Hopf>>,
+    mut gizmos: Gizmos,
+) {
+    // Just above the surface of the sphere.
+    let r = INDICATOR_BALL_RADIUS * 1.01;
+    let mut curve = HopfSurface {
+        n_loops: 64,
+        ..surface.clone()
+    }
+    .seeds();
+    // Seeds stop short of the end, close the curve.
+    curve.push(match surface.seeds {
+        SeedDistribution::Latitude => curve[0],
+        SeedDistribution::Linear | SeedDistribution::Geodesic => surface.line_end,
+    });
+    let points = curve
+        .into_iter()
+        .map(|sp| ball.transform_point(sp.to_cartesian(r)));
+    gizmos.linestrip(points, YELLOW_300);
+}
+
+/// A system highlighting the fibre under the pointer, when it is over the Hopf mesh.
+///
+/// The fibre is traced, its base point marked on the sphere and its
+/// coordinates shown in the readout.
+#[allow(clippy::needless_pass_by_value)]
+fn pick_fibre(
+    rays: Res<RayMap>,
+    mut ray_cast: MeshRayCast,
+    surface: Single<(Entity, &HopfSurface, &GlobalTransform, Option<&Mesh3d>), With<Hopf>>,
+    ball: Single<&GlobalTransform, With<IndicatorBall>>,
+    meshes: Res<Assets<Mesh>>,
+    mut readout: Single<&mut Text, With<HoverReadout>>,
+    mut gizmos: Gizmos,
+) {
+    let (surface_entity, hopf_surface, surface_transform, mesh3d) = *surface;
+    let mesh = mesh3d.and_then(|Mesh3d(handle)| meshes.get(handle));
+
+    // Cast the pointer rays directly, unlike the picking hits these say
+    // which triangle was hit. Only the nearest hit counts, when nothing
+    // else is in front of the surface.
+    let fibre_point = rays.iter().find_map(|(_id, ray)| {
+        let (entity, hit) = ray_cast
+            .cast_ray(*ray, &MeshRayCastSettings::default())
+            .first()?;
+        if *entity != surface_entity {
+            return None;
+        }
+        fibre_point_on_triangle(mesh?, hit.triangle_index?, hit.barycentric_coords)
+    });
+
+    let Some(fibre_point) = fibre_point else {
+        readout.0.clear();
+        return;
+    };
+    let sp = fibre_point.sp;
+    readout.0 = format!(
+        "lat {:.1}\u{b0} lon {:.1}\u{b0} alpha {:.3}",
+        sp.lat.to_degrees(),
+        sp.lon.to_degrees(),
+        fibre_point.alpha
+    );
+
+    let color = fibre_color(sp);
+    let alpha = 0_f32..=F32_4PI;
+    let fibre = Fibre::new(sp, &alpha)
+        .with_rotation(hopf_surface.rotation)
+        .projected_fibre();
+    let n_points = 256_u16;
+    let points = (0..=n_points).map(|i| {
+        let a = F32_4PI * f32::from(i) / f32::from(n_points);
+        surface_transform.transform_point(fibre(a).into())
+    });
+    gizmos.linestrip(points, color);
+    gizmos.sphere(
+        surface_transform.transform_point(fibre(fibre_point.alpha).into()),
+        0.05,
+        color,
+    );
+
+    gizmos.sphere(
+        ball.transform_point(sp.to_cartesian(INDICATOR_BALL_RADIUS)),
+        0.1,
+        color,
+    );
+}
+
+/// A system rotating S3 from the keyboard, both the surface and
+/// the spawned fibres are regenerated as it turns.
+#[allow(clippy::needless_pass_by_value)]
+fn rotate_s3(
+    input: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
+    mut angles: ResMut<S3Angles>,
+    mut surface: Single<&mut HopfSurface, With<Hopf>>,
+    mut fibres: Query<&mut HopfFibre>,
+) {
+    // ( increase, decrease ) in Plane::ALL order.
+    const KEYS: [(KeyCode, KeyCode); 6] = [
+        (KeyCode::KeyR, KeyCode::KeyF),
+        (KeyCode::KeyT, KeyCode::KeyG),
+        (KeyCode::KeyQ, KeyCode::KeyA),
+        (KeyCode::KeyY, KeyCode::KeyH),
+        (KeyCode::KeyW, KeyCode::KeyS),
+        (KeyCode::KeyE, KeyCode::KeyD),
+    ];
+    // Radians per second.
+    const SPEED: f32 = 0.5;
+
+    let mut changed = input.just_pressed(KeyCode::Space);
+    if changed {
+        angles.0 = [0_f32; 6];
+    }
+    for (angle, (up, down)) in angles.0.iter_mut().zip(KEYS) {
+        let direction = match (input.pressed(up), input.pressed(down)) {
+            (true, false) => 1_f32,
+            (false, true) => -1_f32,
+            _ => continue,
+        };
+        *angle = (direction * SPEED).mul_add(time.delta_secs(), *angle);
+        changed = true;
+    }
+    if !changed {
+        return;
+    }
+
+    let rotation = rotation_from_angles(&angles.0);
+    surface.rotation = rotation;
+    for mut fibre in &mut fibres {
+        fibre.rotation = rotation;
+    }
+}
+
+/// A system exporting the Hopf surfaces and fibres, with their parameters alongside.
+#[allow(clippy::needless_pass_by_value)]
+fn export_scene(input: Res<ButtonInput<KeyCode>>, mut exports: MessageWriter<ExportHopfScene>) {
+    if !input.just_pressed(KeyCode::KeyX) {
+        return;
+    }
+    let extension = if input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        "ply"
+    } else {
+        "obj"
+    };
+    exports.write(ExportHopfScene {
+        path: format!("hopf_export.{extension}").into(),
+    });
+}
+
+/// A system toggling the flow along the surface and the spawned fibres.
+#[allow(clippy::needless_pass_by_value, clippy::type_complexity)]
+fn toggle_flow(
+    mut commands: Commands,
+    input: Res<ButtonInput<KeyCode>>,
+    flowing: Query<Entity, With<HopfFlow>>,
+    still: Query<Entity, (Or<(With<HopfSurface>, With<HopfFibre>)>, Without<HopfFlow>)>,
+) {
+    if !input.just_pressed(KeyCode::KeyM) {
+        return;
+    }
+    if flowing.is_empty() {
+        for entity in &still {
+            commands.entity(entity).insert(HopfFlow {
+                color: Color::from(RED_500),
+                ..default()
+            });
+        }
+    } else {
+        for entity in &flowing {
+            commands.entity(entity).remove::<HopfFlow>();
         }
     }
 }
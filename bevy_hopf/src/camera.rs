@@ -0,0 +1,280 @@
+use core::ops::RangeInclusive;
+
+use bevy::camera::primitives::Aabb;
+use bevy::ecs::component::Component;
+use bevy::ecs::hierarchy::ChildOf;
+use bevy::ecs::query::Changed;
+use bevy::ecs::query::With;
+use bevy::ecs::reflect::ReflectComponent;
+use bevy::ecs::resource::Resource;
+use bevy::ecs::system::Query;
+use bevy::ecs::system::Res;
+use bevy::ecs::system::ResMut;
+use bevy::input::ButtonInput;
+use bevy::input::keyboard::KeyCode;
+use bevy::input::mouse::AccumulatedMouseMotion;
+use bevy::input::mouse::AccumulatedMouseScroll;
+use bevy::input::mouse::MouseButton;
+use bevy::input::mouse::MouseScrollUnit;
+use bevy::picking::pointer::PointerId;
+use bevy::picking::pointer::PointerInteraction;
+use bevy::prelude::Vec2;
+use bevy::prelude::Vec3;
+use bevy::reflect::Reflect;
+use bevy::reflect::std_traits::ReflectDefault;
+use bevy::transform::components::GlobalTransform;
+use bevy::transform::components::Transform;
+
+/// A camera orbiting, and looking at, a focus point.
+///
+/// Driven by the [`HopfPlugin`](crate::HopfPlugin) when `orbit_camera` is set.
+/// - Left drag orbits, unless the drag starts on a [`BlocksCameraDrag`] entity.
+/// - Scrolling zooms.
+/// - `frame_key` ( F by default ) frames the entities marked [`HopfSelected`].
+#[derive(Clone, Component, Debug, Reflect)]
+#[reflect(Component, Default, Debug, Clone)]
+pub struct HopfOrbitCamera {
+    /// The point being orbited.
+    pub focus: Vec3,
+    /// Distance from the focus.
+    pub radius: f32,
+    /// Rotation about the Y axis ( radians ).
+    pub yaw: f32,
+    /// Elevation above the XZ plane ( radians ).
+    pub pitch: f32,
+    /// Radians turned per pixel dragged.
+    pub orbit_sensitivity: f32,
+    /// Fraction of the radius zoomed per scrolled line.
+    pub zoom_sensitivity: f32,
+    /// Limits on the radius.
+    pub radius_range: RangeInclusive<f32>,
+    /// The key framing the [`HopfSelected`] entities.
+    pub frame_key: KeyCode,
+}
+
+impl Default for HopfOrbitCamera {
+    fn default() -> Self {
+        Self {
+            focus: Vec3::ZERO,
+            radius: 10.0,
+            yaw: 0.0,
+            pitch: 0.0,
+            orbit_sensitivity: 0.005,
+            zoom_sensitivity: 0.1,
+            radius_range: 0.5..=200.0,
+            frame_key: KeyCode::KeyF,
+        }
+    }
+}
+
+// Keep clear of the poles, where looking_at() is undefined.
+const PITCH_LIMIT: f32 = 89_f32.to_radians();
+
+impl HopfOrbitCamera {
+    /// An orbit camera placed at `eye`, looking at `focus`.
+    #[must_use]
+    pub fn looking_at(eye: Vec3, focus: Vec3) -> Self {
+        let offset = eye - focus;
+        let radius = offset.length();
+        Self {
+            focus,
+            radius,
+            yaw: offset.x.atan2(offset.z),
+            pitch: (offset.y / radius).clamp(-1_f32, 1_f32).asin(),
+            ..Self::default()
+        }
+    }
+
+    /// The camera transform described by the orbit.
+    #[must_use]
+    pub fn transform(&self) -> Transform {
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
+        let offset = Vec3::new(cos_pitch * sin_yaw, sin_pitch, cos_pitch * cos_yaw);
+        Transform::from_translation(self.focus + offset * self.radius)
+            .looking_at(self.focus, Vec3::Y)
+    }
+}
+
+/// Camera drags starting on this entity, or its descendants, are left to picking.
+///
+/// For example gizmo handles which are themselves dragged.
+#[derive(Clone, Component, Copy, Debug, Default, Reflect)]
+#[reflect(Component, Default, Debug, Clone)]
+pub struct BlocksCameraDrag;
+
+/// Entities framed by the orbit camera, when its `frame_key` is pressed.
+#[derive(Clone, Component, Copy, Debug, Default, Reflect)]
+#[reflect(Component, Default, Debug, Clone)]
+pub struct HopfSelected;
+
+/// Whether the current left mouse drag belongs to the camera.
+#[derive(Debug, Default, Resource)]
+pub struct OrbitDrag(bool);
+
+/// Orbits on left drag, and zooms on scroll.
+#[allow(clippy::needless_pass_by_value, clippy::too_many_arguments)]
+pub fn orbit_camera_input(
+    buttons: Res<ButtonInput<MouseButton>>,
+    motion: Res<AccumulatedMouseMotion>,
+    scroll: Res<AccumulatedMouseScroll>,
+    pointers: Query<(&PointerId, &PointerInteraction)>,
+    blockers: Query<(), With<BlocksCameraDrag>>,
+    parents: Query<&ChildOf>,
+    mut drag: ResMut<OrbitDrag>,
+    mut cameras: Query<&mut HopfOrbitCamera>,
+) {
+    if buttons.just_pressed(MouseButton::Left) {
+        // Picking takes priority, when the press lands on a blocker.
+        let blocked = pointers
+            .iter()
+            .filter(|(id, _)| id.is_mouse())
+            .filter_map(|(_, interaction)| interaction.get_nearest_hit())
+            .any(|(entity, _hit)| {
+                core::iter::once(*entity)
+                    .chain(parents.iter_ancestors(*entity))
+                    .any(|e| blockers.contains(e))
+            });
+        drag.0 = !blocked;
+    }
+    if !buttons.pressed(MouseButton::Left) {
+        drag.0 = false;
+    }
+
+    let lines = match scroll.unit {
+        MouseScrollUnit::Line => scroll.delta.y,
+        // Roughly one line.
+        MouseScrollUnit::Pixel => scroll.delta.y / 16_f32,
+    };
+
+    for mut camera in &mut cameras {
+        if drag.0 && motion.delta != Vec2::ZERO {
+            camera.yaw -= motion.delta.x * camera.orbit_sensitivity;
+            camera.pitch = motion
+                .delta
+                .y
+                .mul_add(camera.orbit_sensitivity, camera.pitch)
+                .clamp(-PITCH_LIMIT, PITCH_LIMIT);
+        }
+        if lines != 0_f32 {
+            let radius = camera.radius * (-lines * camera.zoom_sensitivity).exp();
+            camera.radius = radius.clamp(*camera.radius_range.start(), *camera.radius_range.end());
+        }
+    }
+}
+
+/// Frames every [`HopfSelected`] entity when a camera's `frame_key` is pressed.
+///
+/// The focus moves to the centre of their bounds, and the radius
+/// is set so the bounds fill the view.
+#[allow(clippy::needless_pass_by_value)]
+pub fn frame_selected(
+    keys: Res<ButtonInput<KeyCode>>,
+    selected: Query<(&GlobalTransform, Option<&Aabb>), With<HopfSelected>>,
+    mut cameras: Query<&mut HopfOrbitCamera>,
+) {
+    if !cameras
+        .iter()
+        .any(|camera| keys.just_pressed(camera.frame_key))
+    {
+        return;
+    }
+
+    let mut min = Vec3::INFINITY;
+    let mut max = Vec3::NEG_INFINITY;
+    for (transform, aabb) in &selected {
+        let corners = aabb.map_or([Vec3::ZERO; 2], |aabb| {
+            [
+                Vec3::from(aabb.center - aabb.half_extents),
+                Vec3::from(aabb.center + aabb.half_extents),
+            ]
+        });
+        // All eight corners, the transform may rotate the box.
+        for x in [corners[0].x, corners[1].x] {
+            for y in [corners[0].y, corners[1].y] {
+                for z in [corners[0].z, corners[1].z] {
+                    let p = transform.transform_point(Vec3::new(x, y, z));
+                    min = min.min(p);
+                    max = max.max(p);
+                }
+            }
+        }
+    }
+    if !min.is_finite() || !max.is_finite() {
+        return;
+    }
+
+    let focus = (min + max) / 2_f32;
+    let extent = (max - min).length() / 2_f32;
+    for mut camera in &mut cameras {
+        if !keys.just_pressed(camera.frame_key) {
+            continue;
+        }
+        camera.focus = focus;
+        // A bounding sphere fits a 45 degree field of view, with some margin.
+        let radius = (extent * 3_f32).max(*camera.radius_range.start());
+        camera.radius = radius.min(*camera.radius_range.end());
+    }
+}
+
+/// Moves cameras whose orbit has changed.
+pub fn apply_orbit_camera(
+    mut cameras: Query<(&HopfOrbitCamera, &mut Transform), Changed<HopfOrbitCamera>>,
+) {
+    for (camera, mut transform) in &mut cameras {
+        *transform = camera.transform();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn looking_at_round_trip() {
+        let eye = Vec3::new(0.0, 7.0, 14.0);
+        let focus = Vec3::new(0.0, 1.0, 0.0);
+        let camera = HopfOrbitCamera::looking_at(eye, focus);
+        let transform = camera.transform();
+        assert!(transform.translation.abs_diff_eq(eye, 1e-4));
+        assert!(
+            transform
+                .forward()
+                .abs_diff_eq((focus - eye).normalize(), 1e-4)
+        );
+    }
+
+    #[test]
+    fn frames_with_the_camera_key() {
+        use bevy::app::App;
+        use bevy::app::Update;
+
+        let mut app = App::new();
+        app.init_resource::<ButtonInput<KeyCode>>()
+            .add_systems(Update, frame_selected);
+        app.world_mut().spawn((
+            HopfSelected,
+            GlobalTransform::from_translation(Vec3::new(5.0, 0.0, 0.0)),
+        ));
+        let camera = app
+            .world_mut()
+            .spawn(HopfOrbitCamera {
+                frame_key: KeyCode::KeyZ,
+                ..HopfOrbitCamera::default()
+            })
+            .id();
+        let focus = |app: &App| app.world().get::<HopfOrbitCamera>(camera).unwrap().focus;
+
+        let press = |app: &mut App, key| {
+            let mut keys = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+            keys.clear();
+            keys.press(key);
+            app.update();
+        };
+        // Another key leaves the camera alone.
+        press(&mut app, KeyCode::KeyF);
+        assert_eq!(focus(&app), Vec3::ZERO);
+        press(&mut app, KeyCode::KeyZ);
+        assert!(focus(&app).abs_diff_eq(Vec3::new(5.0, 0.0, 0.0), 1e-5));
+    }
+}
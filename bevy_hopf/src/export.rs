@@ -0,0 +1,659 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use bevy::ecs::entity::Entity;
+use bevy::ecs::message::Message;
+use bevy::ecs::message::MessageReader;
+use bevy::ecs::system::Query;
+use bevy::log::info;
+use bevy::log::warn;
+use bevy::math::Affine3A;
+use bevy::prelude::Mat4;
+use bevy::prelude::Vec3;
+use bevy::transform::components::GlobalTransform;
+use hopf::Vertex;
+use hopf::gltf::Gltf;
+use hopf::obj::Obj;
+use hopf::ply::Ply;
+use hopf::ply::PlyFormat;
+use hopf::sp::SurfacePoint;
+use hopf::stl::Stl;
+use hopf::stl::StlFormat;
+use thiserror::Error;
+
+use crate::fibre::HopfFibre;
+use crate::hopf::HopfMeshError;
+use crate::hopf::HopfShading;
+use crate::hopf::HopfSurface;
+use crate::hopf::SeedDistribution;
+
+/// An error when exporting Hopf surfaces, or reading back their parameters.
+#[derive(Debug, Error)]
+pub enum HopfExportError {
+    /// When the file extension is not `.obj`, `.ply`, `.glb` or `.stl`.
+    #[error("Cannot export to {path:?}, expected an .obj, .ply, .glb or .stl extension.")]
+    UnknownFormat {
+        /// The requested output file.
+        path: PathBuf,
+    },
+    /// When a surface cannot be built.
+    #[error(transparent)]
+    Mesh(#[from] HopfMeshError),
+    /// When writing, or reading, a file fails.
+    #[error("Cannot export the Hopf surfaces: {0}")]
+    Io(#[from] std::io::Error),
+    /// When a parameters file is malformed.
+    #[error("Cannot read Hopf parameters, line {line}: {message}")]
+    Params {
+        /// The offending line ( starting at 1 ).
+        line: usize,
+        /// What was wrong.
+        message: String,
+    },
+}
+
+/// File formats the surfaces can be exported to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Wavefront OBJ, one object of quads per sheet or tube.
+    Obj,
+    /// Binary PLY, one quad face per grid cell.
+    Ply,
+    /// Binary glTF, one named node per sheet or tube.
+    Glb,
+    /// Binary STL, for printing, only closed surfaces such as tubes around whole fibres.
+    Stl,
+}
+
+impl ExportFormat {
+    /// The format matching the extension of `path`, ignoring case.
+    #[must_use]
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "obj" => Some(Self::Obj),
+            "ply" => Some(Self::Ply),
+            "glb" => Some(Self::Glb),
+            "stl" => Some(Self::Stl),
+            _ => None,
+        }
+    }
+}
+
+/// Requests an export of every [`HopfSurface`] and [`HopfFibre`] in the scene,
+/// see [`export_surfaces`].
+///
+/// Handled by the [`HopfPlugin`](crate::HopfPlugin).
+#[derive(Clone, Debug, Message)]
+pub struct ExportHopfScene {
+    /// The output file, its extension selects the [`ExportFormat`].
+    pub path: PathBuf,
+}
+
+// Points closer than this are merged before an STL is checked for holes.
+const STL_WELD_TOLERANCE: f32 = 1e-4;
+
+/// Writes the surfaces and fibres, with `transform` applied to every vertex.
+///
+/// The surfaces are rebuilt from their parameters, rather than read back
+/// from the render meshes, so the output does not depend on the shading.
+/// Only the full resolution is written, [`HopfLod`](crate::lod::HopfLod)
+/// levels are coarser copies of their surface.
+///
+/// Fibres are written as polylines, except to STL which only holds closed
+/// surfaces, there they are skipped.
+///
+/// # Errors
+///
+/// `HopfExportError::Mesh` if a surface or fibre cannot be built.
+///
+/// `HopfExportError::Io` when writing to the buffer fails,
+/// or an STL surface is not closed.
+pub fn write_surfaces<W>(
+    surfaces: &[(HopfSurface, Affine3A)],
+    fibres: &[(HopfFibre, Affine3A)],
+    format: ExportFormat,
+    out: &mut BufWriter<W>,
+) -> Result<(), HopfExportError>
+where
+    W: ?Sized + std::io::Write,
+{
+    let mut obj = Obj::default();
+    let mut ply = Ply::default();
+    let mut gltf = Gltf::default();
+    let mut stl = Stl::default();
+    for (i, (surface, transform)) in surfaces.iter().enumerate() {
+        for (j, (mut grid, _fibres)) in surface.fibre_grids()?.into_iter().enumerate() {
+            for v in &mut grid.vertices {
+                *v = Vertex(transform.transform_point3(Vec3::from(*v)));
+            }
+            match format {
+                ExportFormat::Obj => obj.push_grid(format!("surface_{i}_{j}"), &grid),
+                ExportFormat::Ply => ply.push_grid(&grid)?,
+                ExportFormat::Glb => gltf.push_grid(format!("surface_{i}_{j}"), &grid)?,
+                ExportFormat::Stl => stl.push_grid(&grid),
+            }
+        }
+    }
+    if format != ExportFormat::Stl {
+        for (i, (fibre, transform)) in fibres.iter().enumerate() {
+            let points = fibre
+                .points()
+                .map_err(|e| HopfMeshError::from_fibre(&e, fibre.sp))?
+                .into_iter()
+                .map(|v| Vertex(transform.transform_point3(Vec3::from(v))))
+                .collect::<Vec<_>>();
+            match format {
+                ExportFormat::Obj => obj.push_polyline(&format!("fibre_{i}"), &points),
+                ExportFormat::Ply => ply.push_polyline(&points)?,
+                ExportFormat::Glb => gltf.push_polyline(format!("fibre_{i}"), &points)?,
+                ExportFormat::Stl => {}
+            }
+        }
+    }
+
+    match format {
+        ExportFormat::Obj => obj.write(out)?,
+        ExportFormat::Ply => ply.write(PlyFormat::BinaryLittleEndian, out)?,
+        ExportFormat::Glb => gltf.write_glb(out)?,
+        ExportFormat::Stl => {
+            // Join the seams where loops, and tubes, repeat their first point.
+            stl.weld(STL_WELD_TOLERANCE);
+            stl.write(StlFormat::Binary, out)?;
+        }
+    }
+    Ok(())
+}
+
+// Formats a list of floats as `[a, b, c]`.
+//
+// Display prints the shortest representation that reads back exactly.
+fn array(values: &[f32]) -> String {
+    let values = values.iter().map(ToString::to_string).collect::<Vec<_>>();
+    format!("[{}]", values.join(", "))
+}
+
+/// The surfaces and fibres described by a `.params` sidecar, see [`read_params`].
+#[derive(Clone, Debug, Default)]
+pub struct HopfParams {
+    /// Each surface, with its transform.
+    pub surfaces: Vec<(HopfSurface, Affine3A)>,
+    /// Each fibre, with its transform.
+    pub fibres: Vec<(HopfFibre, Affine3A)>,
+}
+
+/// Writes the parameters of each surface and fibre, and its transform.
+///
+/// A TOML compatible list of `[[surface]]` tables, followed by
+/// `[[fibre]]` tables, see [`read_params`].
+///
+/// # Errors
+///
+/// When writing to the buffer fails.
+pub fn write_params<W>(
+    surfaces: &[(HopfSurface, Affine3A)],
+    fibres: &[(HopfFibre, Affine3A)],
+    out: &mut BufWriter<W>,
+) -> Result<(), std::io::Error>
+where
+    W: ?Sized + std::io::Write,
+{
+    writeln!(
+        out,
+        "# Hopf surfaces and fibres, angles in radians, matrices column major."
+    )?;
+    for (surface, transform) in surfaces {
+        let HopfSurface {
+            line_start,
+            line_end,
+            alpha,
+            n_points_per_loop,
+            n_loops,
+            seeds,
+            tube_radius,
+            thickness,
+            shading,
+            sampling,
+            rotation,
+        } = surface;
+        writeln!(out)?;
+        writeln!(out, "[[surface]]")?;
+        writeln!(
+            out,
+            "line_start = {}",
+            array(&[line_start.lat, line_start.lon])
+        )?;
+        writeln!(out, "line_end = {}", array(&[line_end.lat, line_end.lon]))?;
+        writeln!(out, "alpha = {}", array(&[*alpha.start(), *alpha.end()]))?;
+        writeln!(out, "n_points_per_loop = {n_points_per_loop}")?;
+        writeln!(out, "n_loops = {n_loops}")?;
+        writeln!(out, "seeds = \"{seeds:?}\"")?;
+        writeln!(out, "tube_radius = {tube_radius}")?;
+        writeln!(out, "thickness = {thickness}")?;
+        writeln!(out, "shading = \"{shading:?}\"")?;
+        writeln!(out, "n_tries = {}", sampling.n_tries)?;
+        writeln!(out, "tolerance = {}", sampling.tolerance)?;
+        writeln!(out, "rotation = {}", array(&rotation.to_cols_array()))?;
+        writeln!(out, "transform = {}", array(&transform.to_cols_array()))?;
+    }
+    for (fibre, transform) in fibres {
+        let HopfFibre {
+            sp,
+            alpha,
+            n_points,
+            sampling,
+            rotation,
+        } = fibre;
+        writeln!(out)?;
+        writeln!(out, "[[fibre]]")?;
+        writeln!(out, "sp = {}", array(&[sp.lat, sp.lon]))?;
+        writeln!(out, "alpha = {}", array(&[*alpha.start(), *alpha.end()]))?;
+        writeln!(out, "n_points = {n_points}")?;
+        writeln!(out, "n_tries = {}", sampling.n_tries)?;
+        writeln!(out, "tolerance = {}", sampling.tolerance)?;
+        writeln!(out, "rotation = {}", array(&rotation.to_cols_array()))?;
+        writeln!(out, "transform = {}", array(&transform.to_cols_array()))?;
+    }
+    Ok(())
+}
+
+fn floats<const N: usize>(value: &str) -> Result<[f32; N], String> {
+    let inner = value
+        .strip_prefix('[')
+        .and_then(|v| v.strip_suffix(']'))
+        .ok_or_else(|| format!("expected a list, found {value}"))?;
+    let values = inner
+        .split(',')
+        .map(|v| v.trim().parse::<f32>().map_err(|e| format!("{v}: {e}")))
+        .collect::<Result<Vec<_>, _>>()?;
+    let n = values.len();
+    values
+        .try_into()
+        .map_err(|_| format!("expected {N} values, found {n}"))
+}
+
+fn parse<T>(value: &str) -> Result<T, String>
+where
+    T: core::str::FromStr,
+    T::Err: core::fmt::Display,
+{
+    value.parse::<T>().map_err(|e| format!("{value}: {e}"))
+}
+
+fn set_param(
+    surface: &mut HopfSurface,
+    transform: &mut Affine3A,
+    key: &str,
+    value: &str,
+) -> Result<(), String> {
+    match key {
+        "line_start" | "line_end" => {
+            let [lat, lon] = floats(value)?;
+            let sp = SurfacePoint { lat, lon };
+            if key == "line_start" {
+                surface.line_start = sp;
+            } else {
+                surface.line_end = sp;
+            }
+        }
+        "alpha" => {
+            let [start, end] = floats(value)?;
+            surface.alpha = start..=end;
+        }
+        "n_points_per_loop" => surface.n_points_per_loop = parse(value)?,
+        "n_loops" => surface.n_loops = parse(value)?,
+        "seeds" => {
+            surface.seeds = match value.trim_matches('"') {
+                "Linear" => SeedDistribution::Linear,
+                "Geodesic" => SeedDistribution::Geodesic,
+                "Latitude" => SeedDistribution::Latitude,
+                other => return Err(format!("unknown seed distribution {other}")),
+            };
+        }
+        "tube_radius" => surface.tube_radius = parse(value)?,
+        "thickness" => surface.thickness = parse(value)?,
+        "shading" => {
+            surface.shading = match value.trim_matches('"') {
+                "Flat" => HopfShading::Flat,
+                "Smooth" => HopfShading::Smooth,
+                other => return Err(format!("unknown shading {other}")),
+            };
+        }
+        "n_tries" => surface.sampling.n_tries = parse(value)?,
+        "tolerance" => surface.sampling.tolerance = parse(value)?,
+        "rotation" => surface.rotation = Mat4::from_cols_array(&floats(value)?),
+        "transform" => *transform = Affine3A::from_cols_array(&floats(value)?),
+        _ => return Err(format!("unknown key {key}")),
+    }
+    Ok(())
+}
+
+fn set_fibre_param(
+    fibre: &mut HopfFibre,
+    transform: &mut Affine3A,
+    key: &str,
+    value: &str,
+) -> Result<(), String> {
+    match key {
+        "sp" => {
+            let [lat, lon] = floats(value)?;
+            fibre.sp = SurfacePoint { lat, lon };
+        }
+        "alpha" => {
+            let [start, end] = floats(value)?;
+            fibre.alpha = start..=end;
+        }
+        "n_points" => fibre.n_points = parse(value)?,
+        "n_tries" => fibre.sampling.n_tries = parse(value)?,
+        "tolerance" => fibre.sampling.tolerance = parse(value)?,
+        "rotation" => fibre.rotation = Mat4::from_cols_array(&floats(value)?),
+        "transform" => *transform = Affine3A::from_cols_array(&floats(value)?),
+        _ => return Err(format!("unknown key {key}")),
+    }
+    Ok(())
+}
+
+/// Reads back the surfaces and fibres written by [`write_params`].
+///
+/// Missing keys take their default value, so the file may be edited by hand.
+/// Pass the result to [`write_surfaces`] to reproduce an export headlessly.
+///
+/// # Errors
+///
+/// `HopfExportError::Params` on the first malformed line.
+pub fn read_params(text: &str) -> Result<HopfParams, HopfExportError> {
+    let mut params = HopfParams::default();
+    // Keys belong to the most recent table.
+    let mut in_fibre = None;
+    for (i, line) in text.lines().enumerate() {
+        let error = |message: String| HopfExportError::Params {
+            line: i + 1,
+            message,
+        };
+
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line == "[[surface]]" {
+            params
+                .surfaces
+                .push((HopfSurface::default(), Affine3A::IDENTITY));
+            in_fibre = Some(false);
+            continue;
+        }
+        if line == "[[fibre]]" {
+            params
+                .fibres
+                .push((HopfFibre::default(), Affine3A::IDENTITY));
+            in_fibre = Some(true);
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(error(format!("expected key = value, found {line}")));
+        };
+        let (key, value) = (key.trim(), value.trim());
+        match (
+            in_fibre,
+            params.surfaces.last_mut(),
+            params.fibres.last_mut(),
+        ) {
+            (Some(false), Some((surface, transform)), _) => {
+                set_param(surface, transform, key, value).map_err(error)?;
+            }
+            (Some(true), _, Some((fibre, transform))) => {
+                set_fibre_param(fibre, transform, key, value).map_err(error)?;
+            }
+            _ => {
+                return Err(error(
+                    "expected [[surface]] or [[fibre]] before any keys".to_string(),
+                ));
+            }
+        }
+    }
+    Ok(params)
+}
+
+/// Writes `surfaces` and `fibres` to `path`, and their parameters to a
+/// sidecar file next to it.
+///
+/// The format follows the extension of `path`, see [`ExportFormat::from_path`].
+/// The sidecar shares the file stem, with a `.params` extension.
+/// Returns the path of the sidecar.
+///
+/// # Errors
+///
+/// `HopfExportError::UnknownFormat` for an unsupported extension,
+/// otherwise see [`write_surfaces`].
+pub fn export_surfaces(
+    surfaces: &[(HopfSurface, Affine3A)],
+    fibres: &[(HopfFibre, Affine3A)],
+    path: &Path,
+) -> Result<PathBuf, HopfExportError> {
+    let format = ExportFormat::from_path(path).ok_or_else(|| HopfExportError::UnknownFormat {
+        path: path.to_path_buf(),
+    })?;
+
+    let mut out = BufWriter::new(File::create(path)?);
+    write_surfaces(surfaces, fibres, format, &mut out)?;
+    out.flush()?;
+
+    let params_path = path.with_extension("params");
+    let mut out = BufWriter::new(File::create(&params_path)?);
+    write_params(surfaces, fibres, &mut out)?;
+    out.flush()?;
+
+    Ok(params_path)
+}
+
+/// Handles [`ExportHopfScene`] requests.
+///
+/// Surfaces and fibres are written in entity order, with their global
+/// transform applied, see [`write_surfaces`].
+/// The export blocks the frame, it is expected to be rare.
+#[allow(clippy::needless_pass_by_value)]
+pub fn export_hopf_scene(
+    mut requests: MessageReader<ExportHopfScene>,
+    surfaces: Query<(Entity, &HopfSurface, &GlobalTransform)>,
+    fibres: Query<(Entity, &HopfFibre, &GlobalTransform)>,
+) {
+    for ExportHopfScene { path } in requests.read() {
+        let surfaces = ordered(surfaces.iter());
+        let fibres = ordered(fibres.iter());
+        match export_surfaces(&surfaces, &fibres, path) {
+            Ok(params_path) => info!(
+                "Exported {} Hopf surfaces and {} fibres to {path:?}, parameters in {params_path:?}",
+                surfaces.len(),
+                fibres.len()
+            ),
+            Err(e) => warn!("{e}"),
+        }
+    }
+}
+
+// Components in entity order, with their global transforms.
+fn ordered<'a, T: Clone + 'a>(
+    items: impl Iterator<Item = (Entity, &'a T, &'a GlobalTransform)>,
+) -> Vec<(T, Affine3A)> {
+    let mut items = items.collect::<Vec<_>>();
+    items.sort_by_key(|(entity, _, _)| *entity);
+    items
+        .into_iter()
+        .map(|(_, item, transform)| (item.clone(), transform.affine()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scene() -> Vec<(HopfSurface, Affine3A)> {
+        vec![
+            (
+                HopfSurface {
+                    n_loops: 4,
+                    n_points_per_loop: 8,
+                    ..HopfSurface::default()
+                },
+                Affine3A::from_translation(Vec3::new(0.0, 3.0, 0.0)),
+            ),
+            (
+                HopfSurface {
+                    n_loops: 3,
+                    n_points_per_loop: 6,
+                    alpha: 0.5..=2.5,
+                    seeds: SeedDistribution::Geodesic,
+                    tube_radius: 0.05,
+                    shading: HopfShading::Smooth,
+                    rotation: hopf::rotation::rotation_from_angles(&[0.1, 0.2, 0.3, 0.4, 0.5, 0.6]),
+                    ..HopfSurface::default()
+                },
+                Affine3A::from_scale(Vec3::splat(0.8)),
+            ),
+        ]
+    }
+
+    #[test]
+    fn params_round_trip() {
+        let surfaces = scene();
+        let fibres = vec![
+            (HopfFibre::default(), Affine3A::IDENTITY),
+            (
+                HopfFibre {
+                    sp: SurfacePoint {
+                        lat: -0.3,
+                        lon: 2.0,
+                    },
+                    alpha: 1.0..=3.0,
+                    n_points: 33,
+                    rotation: hopf::rotation::rotation_from_angles(&[0.6, 0.5, 0.4, 0.3, 0.2, 0.1]),
+                    ..HopfFibre::default()
+                },
+                Affine3A::from_translation(Vec3::new(1.0, -2.0, 0.5)),
+            ),
+        ];
+        let mut out = BufWriter::new(Vec::new());
+        write_params(&surfaces, &fibres, &mut out).unwrap();
+        let text = String::from_utf8(out.into_inner().unwrap()).unwrap();
+
+        let read = read_params(&text).unwrap();
+        assert_eq!(read.surfaces.len(), surfaces.len());
+        for ((a, a_transform), (b, b_transform)) in surfaces.iter().zip(&read.surfaces) {
+            assert_eq!(format!("{a:?}"), format!("{b:?}"));
+            assert_eq!(a_transform, b_transform);
+        }
+        assert_eq!(read.fibres.len(), fibres.len());
+        for ((a, a_transform), (b, b_transform)) in fibres.iter().zip(&read.fibres) {
+            assert_eq!(format!("{a:?}"), format!("{b:?}"));
+            assert_eq!(a_transform, b_transform);
+        }
+
+        assert!(matches!(
+            read_params("n_loops = 3"),
+            Err(HopfExportError::Params { line: 1, .. })
+        ));
+    }
+
+    fn obj_vertices(surfaces: &[(HopfSurface, Affine3A)]) -> (Vec<Vec3>, usize) {
+        let mut out = BufWriter::new(Vec::new());
+        write_surfaces(surfaces, &[], ExportFormat::Obj, &mut out).unwrap();
+        let text = String::from_utf8(out.into_inner().unwrap()).unwrap();
+        let vertices = text
+            .lines()
+            .filter_map(|line| line.strip_prefix("v "))
+            .map(|v| {
+                let [x, y, z] = floats(&format!("[{}]", v.replace(' ', ","))).unwrap();
+                Vec3::new(x, y, z)
+            })
+            .collect();
+        let n_faces = text.lines().filter(|l| l.starts_with("f ")).count();
+        (vertices, n_faces)
+    }
+
+    #[test]
+    fn glb_names_each_grid() {
+        let mut out = BufWriter::new(vec![]);
+        let fibres = [(HopfFibre::default(), Affine3A::IDENTITY)];
+        write_surfaces(&scene(), &fibres, ExportFormat::Glb, &mut out).unwrap();
+        let glb = out.into_inner().unwrap();
+        assert_eq!(&glb[..4], b"glTF");
+        let json = String::from_utf8_lossy(&glb[20..]);
+        assert!(json.contains(r#""name":"surface_1_0""#));
+        assert!(json.contains(r#""name":"fibre_0""#));
+        assert_eq!(
+            ExportFormat::from_path(Path::new("scene.GLB")),
+            Some(ExportFormat::Glb)
+        );
+    }
+
+    #[test]
+    fn stl_needs_closed_surfaces() {
+        // Tubes around whole fibres are tori.
+        let tubes = HopfSurface {
+            line_end: SurfacePoint {
+                lat: 45_f32.to_radians(),
+                lon: core::f32::consts::PI,
+            },
+            n_loops: 3,
+            n_points_per_loop: 12,
+            tube_radius: 0.05,
+            ..HopfSurface::default()
+        };
+        let mut out = BufWriter::new(vec![]);
+        // Fibres have no volume, they are left out.
+        let fibres = [(HopfFibre::default(), Affine3A::IDENTITY)];
+        write_surfaces(
+            &[(tubes.clone(), Affine3A::IDENTITY)],
+            &fibres,
+            ExportFormat::Stl,
+            &mut out,
+        )
+        .unwrap();
+        assert!(out.into_inner().unwrap().len() > 84);
+
+        // An open sheet is refused.
+        let e = write_surfaces(
+            &scene()[..1],
+            &[],
+            ExportFormat::Stl,
+            &mut BufWriter::new(vec![]),
+        )
+        .unwrap_err();
+        assert!(matches!(e, HopfExportError::Io(e) if e.kind() == std::io::ErrorKind::InvalidData));
+
+        // Until it is thickened.
+        let shell = HopfSurface {
+            tube_radius: 0.0,
+            thickness: 0.02,
+            n_loops: 6,
+            ..tubes
+        };
+        let mut out = BufWriter::new(vec![]);
+        write_surfaces(
+            &[(shell, Affine3A::IDENTITY)],
+            &[],
+            ExportFormat::Stl,
+            &mut out,
+        )
+        .unwrap();
+        assert!(out.into_inner().unwrap().len() > 84);
+    }
+
+    #[test]
+    fn obj_applies_transforms() {
+        let lifted = scene().swap_remove(0);
+        let (moved, n_faces) = obj_vertices(core::slice::from_ref(&lifted));
+        let (origin, _) = obj_vertices(&[(lifted.0, Affine3A::IDENTITY)]);
+
+        assert_eq!(moved.len(), 4 * 8);
+        // 3 rows of quads between 4 loops of 8 points.
+        assert_eq!(n_faces, 3 * 7);
+        for (a, b) in moved.iter().zip(&origin) {
+            assert!((*a - *b).abs_diff_eq(Vec3::new(0.0, 3.0, 0.0), 1e-4));
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use core::ops::RangeInclusive;
+
+use bevy::asset::RenderAssetUsages;
+use bevy::ecs::component::Component;
+use bevy::ecs::reflect::ReflectComponent;
+use bevy::log::warn;
+use bevy::math::primitives::Primitive3d;
+use bevy::prelude::Mat4;
+use bevy::prelude::Vec3;
+use bevy::reflect::Reflect;
+use bevy::reflect::std_traits::ReflectDefault;
+use bevy_mesh::Mesh;
+use bevy_mesh::MeshBuilder;
+use bevy_mesh::Meshable;
+use bevy_mesh::PrimitiveTopology;
+use hopf::F32_4PI;
+use hopf::Vertex;
+use hopf::fibre::Fibre;
+use hopf::fibre::FibreBuildError;
+use hopf::sp::SurfacePoint;
+
+use crate::hopf::HopfSampling;
+use crate::hopf::SurfacePointDef;
+
+/// A single fibre primitive, the lift of one point on s2.
+///
+/// Meshed as a line strip, which closes on itself when `alpha` spans 0..=4PI.
+///
+/// ```ignore
+/// let handle = meshes.add(HopfFibre {
+///     sp: SurfacePoint::from_cartesian(hit),
+///     ..default()
+/// });
+/// ```
+#[derive(Clone, Component, Debug, Reflect)]
+#[reflect(Component, Default, Debug, Clone)]
+pub struct HopfFibre {
+    /// The point on s2 being lifted.
+    #[reflect(remote = SurfacePointDef)]
+    pub sp: SurfacePoint,
+    /// The domain of the fibre, must be contained by 0..=4PI.
+    pub alpha: RangeInclusive<f32>,
+    /// Number of points along the fibre.
+    pub n_points: usize,
+    /// Sampling options.
+    pub sampling: HopfSampling,
+    /// Rotation of S3 applied before projection, as in [`HopfSurface`](crate::hopf::HopfSurface).
+    pub rotation: Mat4,
+}
+
+impl Default for HopfFibre {
+    fn default() -> Self {
+        Self {
+            sp: SurfacePoint {
+                lat: 45_f32.to_radians(),
+                lon: 0.0,
+            },
+            alpha: 0_f32..=F32_4PI,
+            n_points: 120,
+            sampling: HopfSampling::default(),
+            rotation: Mat4::IDENTITY,
+        }
+    }
+}
+
+impl Primitive3d for HopfFibre {}
+
+impl HopfFibre {
+    /// Points evenly sampled along the fibre.
+    ///
+    /// # Errors
+    ///
+    /// When the fibre cannot be sampled, see [`Fibre::try_build_uniform`].
+    pub fn points(&self) -> Result<Vec<Vertex>, FibreBuildError> {
+        Fibre::new(self.sp, &self.alpha)
+            .with_rotation(self.rotation)
+            .try_build_uniform(
+                self.n_points,
+                self.sampling.tolerance,
+                self.sampling.n_tries,
+            )
+            .map(|(points, _alphas)| points)
+    }
+}
+
+/// A builder used for creating a [`Mesh`] with an [`HopfFibre`] shape.
+#[derive(Clone, Debug)]
+pub struct HopfFibreMeshBuilder {
+    fibre: HopfFibre,
+}
+
+impl MeshBuilder for HopfFibreMeshBuilder {
+    /// Builds a line strip [`Mesh`], evenly sampled along the fibre.
+    ///
+    /// As build cannot fail, a sampling error is logged and an empty mesh returned.
+    fn build(&self) -> Mesh {
+        let positions = match self.fibre.points() {
+            Ok(points) => points.into_iter().map(Vec3::from).collect(),
+            Err(e) => {
+                warn!("Cannot create an HopfFibre at {}: {e}", self.fibre.sp);
+                Vec::<Vec3>::new()
+            }
+        };
+
+        Mesh::new(PrimitiveTopology::LineStrip, RenderAssetUsages::default())
+            .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    }
+}
+
+impl Meshable for HopfFibre {
+    type Output = HopfFibreMeshBuilder;
+
+    fn mesh(&self) -> Self::Output {
+        HopfFibreMeshBuilder {
+            fibre: self.clone(),
+        }
+    }
+}
+
+impl From<HopfFibre> for Mesh {
+    fn from(fibre: HopfFibre) -> Self {
+        fibre.mesh().build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_mesh::VertexAttributeValues;
+
+    use super::*;
+
+    #[test]
+    fn closed_line_strip() {
+        let mesh = Mesh::from(HopfFibre {
+            n_points: 50,
+            ..HopfFibre::default()
+        });
+        assert_eq!(mesh.primitive_topology(), PrimitiveTopology::LineStrip);
+
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            panic!("missing positions");
+        };
+        assert_eq!(positions.len(), 50);
+        // The full 0..=4PI range returns to its start.
+        let first = Vec3::from(positions[0]);
+        let last = Vec3::from(positions[49]);
+        assert!(first.distance(last) < 1e-4);
+    }
+}
@@ -0,0 +1,147 @@
+use core::ops::RangeInclusive;
+
+use bevy::app::App;
+use bevy::app::Plugin;
+use bevy::app::Update;
+use bevy::color::Color;
+use bevy::ecs::component::Component;
+use bevy::ecs::query::AnyOf;
+use bevy::ecs::reflect::ReflectComponent;
+use bevy::ecs::system::Query;
+use bevy::ecs::system::Res;
+use bevy::gizmos::gizmos::Gizmos;
+use bevy::reflect::Reflect;
+use bevy::reflect::std_traits::ReflectDefault;
+use bevy::time::Time;
+use bevy::transform::components::GlobalTransform;
+use hopf::F32_4PI;
+use hopf::fibre::Fibre;
+use hopf::sp::SurfacePoint;
+
+use crate::fibre::HopfFibre;
+use crate::hopf::HopfSurface;
+
+/// Animates the U(1) action, dashes travel along every fibre in sync.
+///
+/// Added next to a [`HopfSurface`] ( one flow per loop ) or a [`HopfFibre`].
+/// Drawn with gizmos by the [`HopfFlowPlugin`].
+#[derive(Clone, Component, Debug, Reflect)]
+#[reflect(Component, Default, Debug, Clone)]
+pub struct HopfFlow {
+    /// Radians of alpha advanced per second.
+    pub speed: f32,
+    /// Number of dashes spread evenly around each full fibre.
+    pub n_dashes: u16,
+    /// Length of each dash, in radians of alpha.
+    pub dash_length: f32,
+    /// Colour of the dashes.
+    pub color: Color,
+}
+
+impl Default for HopfFlow {
+    fn default() -> Self {
+        Self {
+            speed: 1.0,
+            n_dashes: 4,
+            dash_length: 0.4,
+            color: Color::WHITE,
+        }
+    }
+}
+
+/// Draws a [`HopfFlow`], every frame.
+///
+/// Separate from [`HopfPlugin`](crate::HopfPlugin) as it requires the gizmo plugin.
+#[derive(Debug)]
+pub struct HopfFlowPlugin;
+
+impl Plugin for HopfFlowPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<HopfFlow>()
+            .add_systems(Update, draw_hopf_flow);
+    }
+}
+
+// Segments per dash.
+const DASH_SEGMENTS: u16 = 6;
+
+// The start of each dash which lies within `alpha`.
+//
+// Every fibre shares the same phase, so the dashes stay in sync.
+fn dash_starts(
+    flow: &HopfFlow,
+    phase: f32,
+    alpha: &RangeInclusive<f32>,
+) -> impl use<> + Iterator<Item = f32> {
+    let spacing = F32_4PI / f32::from(flow.n_dashes.max(1));
+    let alpha = alpha.clone();
+    (0..flow.n_dashes)
+        .map(move |i| f32::from(i).mul_add(spacing, phase).rem_euclid(F32_4PI))
+        .filter(move |start| alpha.contains(start))
+}
+
+/// Draws the dashes of every [`HopfFlow`].
+///
+/// The projected fibre is cheap to evaluate, so dashes are
+/// recomputed each frame rather than cached.
+#[allow(clippy::needless_pass_by_value, clippy::type_complexity)]
+pub fn draw_hopf_flow(
+    time: Res<Time>,
+    flows: Query<(
+        &HopfFlow,
+        &GlobalTransform,
+        AnyOf<(&HopfSurface, &HopfFibre)>,
+    )>,
+    mut gizmos: Gizmos,
+) {
+    for (flow, transform, (surface, fibre)) in &flows {
+        // Wrapped in f64, so the phase stays accurate in long sessions.
+        #[allow(clippy::cast_possible_truncation)]
+        let phase =
+            (f64::from(flow.speed) * time.elapsed_secs_f64()).rem_euclid(f64::from(F32_4PI)) as f32;
+
+        let (seeds, alpha, rotation): (Vec<SurfacePoint>, _, _) = match (surface, fibre) {
+            (Some(surface), _) => (surface.seeds(), surface.alpha.clone(), surface.rotation),
+            (None, Some(fibre)) => (vec![fibre.sp], fibre.alpha.clone(), fibre.rotation),
+            (None, None) => continue,
+        };
+
+        for sp in seeds {
+            let fibre = Fibre::new(sp, &alpha)
+                .with_rotation(rotation)
+                .projected_fibre();
+            for start in dash_starts(flow, phase, &alpha) {
+                let end = (start + flow.dash_length).min(*alpha.end());
+                let step = (end - start) / f32::from(DASH_SEGMENTS);
+                // A dash running into the projection pole is cut short there.
+                let points = (0..=DASH_SEGMENTS)
+                    .map(|i| fibre(f32::from(i).mul_add(step, start)))
+                    .take_while(|v| v.0.is_finite())
+                    .map(|v| transform.transform_point(v.into()));
+                gizmos.linestrip(points, flow.color);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dashes_are_spaced_and_wrap() {
+        let flow = HopfFlow {
+            n_dashes: 4,
+            ..HopfFlow::default()
+        };
+        let full = 0_f32..=F32_4PI;
+        let starts = dash_starts(&flow, F32_4PI - 1_f32, &full).collect::<Vec<_>>();
+        assert_eq!(starts.len(), 4);
+        // The first dash has wrapped past 4PI.
+        assert!((starts[1] - (core::f32::consts::PI - 1_f32)).abs() < 1e-5);
+
+        // Only dashes starting within a partial surface are drawn.
+        let half = 0_f32..=core::f32::consts::TAU;
+        assert_eq!(dash_starts(&flow, 0.5, &half).count(), 2);
+    }
+}
@@ -1,7 +1,14 @@
-use std::collections::HashMap;
+use core::ops::RangeInclusive;
 
 use bevy::asset::RenderAssetUsages;
+use bevy::ecs::component::Component;
+use bevy::ecs::reflect::ReflectComponent;
+use bevy::log::warn;
+use bevy::math::primitives::Primitive3d;
+use bevy::prelude::Mat4;
 use bevy::prelude::Vec3;
+use bevy::reflect::Reflect;
+use bevy::reflect::std_traits::ReflectDefault;
 use bevy_mesh::Indices;
 use bevy_mesh::Mesh;
 use bevy_mesh::MeshBuilder;
@@ -12,7 +19,16 @@ use thiserror::Error;
 
 use hopf::Vertex;
 use hopf::fibre::Fibre;
+use hopf::fibre::FibreBuildError;
+use hopf::mesh::Grid;
+use hopf::mesh::SolidifyError;
 use hopf::sp::SurfacePoint;
+use hopf::weld::weld;
+
+use crate::pick::ATTRIBUTE_HOPF_FIBRE;
+
+/// Default target accuracy of each loop, see [`HopfSampling::tolerance`].
+pub const DEFAULT_TOLERANCE: f32 = 1e-3;
 
 /// An error when creating an hopf [`Mesh`] from a [`HopfMeshBuilder`].
 #[derive(Clone, Copy, Debug, Error)]
@@ -25,6 +41,22 @@ pub enum HopfMeshError {
         /// The current point being used to generate the mesh.
         sp: SurfacePoint,
     },
+    /// When a loop passes through the projection pole, and cannot be evaluated.
+    #[error("Cannot create an HopfMesh, the loop at {sp} passes through the projection pole.")]
+    PoleError {
+        /// The seed point of the loop.
+        sp: SurfacePoint,
+    },
+    /// When a thickened sheet has a vertex without a normal, see [`Grid::solidify`].
+    #[error(
+        "Cannot create an HopfMesh, the sheet cannot be thickened where the loop at {sp} reaches alpha {alpha}."
+    )]
+    NormalError {
+        /// The seed point of the loop.
+        sp: SurfacePoint,
+        /// Where along the loop the normal is undefined.
+        alpha: f32,
+    },
     /// When the start and end of the line segment are the same.
     #[error("Cannot create an HopfMesh due to invalid line specification.")]
     LineError {
@@ -33,17 +65,157 @@ pub enum HopfMeshError {
         /// The end of the line segment.
         lines_end: SurfacePoint,
     },
+    /// When the alpha range is not contained by 0..=4PI.
+    #[error("Cannot create an HopfMesh, alpha {alpha_start}..={alpha_end} is not within 0..=4PI.")]
+    AlphaError {
+        /// The start of the alpha range.
+        alpha_start: f32,
+        /// The end of the alpha range.
+        alpha_end: f32,
+    },
+    /// When the mesh holds more vertices than can be indexed.
+    #[error("Cannot create an HopfMesh, {n_vertices} vertices cannot be indexed by a u32.")]
+    IndexOverflow {
+        /// The number of vertices in the mesh.
+        n_vertices: usize,
+    },
 }
 
-// #[derive(Clone, Copy, Debug, Reflect)]
-#[derive(Clone, Debug)]
-struct Hopf {
-    line_start: SurfacePoint,
-    line_end: SurfacePoint,
-    n_loops: u16,
+impl HopfMeshError {
+    // The loop seeded at `sp` could not be sampled.
+    pub(crate) const fn from_fibre(e: &FibreBuildError, sp: SurfacePoint) -> Self {
+        match *e {
+            FibreBuildError::NTriesExceed(n_tries) | FibreBuildError::NTriesTooLow(n_tries) => {
+                Self::NRetriesExceeded { n_tries, sp }
+            }
+            FibreBuildError::NotFinite => Self::PoleError { sp },
+        }
+    }
+}
+
+// reflect_remote expands to a transmute between the wrapper and the remote type.
+#[allow(clippy::transmute_ptr_to_ptr)]
+mod remote {
+    use bevy::reflect::reflect_remote;
+    use hopf::sp::SurfacePoint;
+
+    /// Reflection of [`SurfacePoint`], which lives in a crate without bevy.
+    #[reflect_remote(SurfacePoint)]
+    pub struct SurfacePointDef {
+        /// latitude ( radians )
+        pub lat: f32,
+        /// longitude ( radians )
+        pub lon: f32,
+    }
 }
+pub use remote::SurfacePointDef;
 
-impl Default for Hopf {
+/// Options controlling how each loop is sampled.
+#[derive(Clone, Copy, Debug, PartialEq, Reflect)]
+#[reflect(Default, Debug, Clone)]
+pub struct HopfSampling {
+    /// Number of tries when building a individual loop.
+    pub n_tries: u16,
+    /// Target accuracy of each loop.
+    ///
+    /// The relative spread in the spacing between neighbouring points.
+    pub tolerance: f32,
+}
+
+impl Default for HopfSampling {
+    fn default() -> Self {
+        Self {
+            n_tries: 2000,
+            tolerance: DEFAULT_TOLERANCE,
+        }
+    }
+}
+
+/// How the seed points of a [`HopfSurface`] are spread over s2.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Default, Debug, Clone)]
+pub enum SeedDistribution {
+    /// Linear in lat/lon, from `line_start` towards `line_end`.
+    #[default]
+    Linear,
+    /// Along the great circle, from `line_start` towards `line_end`.
+    Geodesic,
+    /// A full circle of latitude through `line_start`, ignoring `line_end`.
+    ///
+    /// The fibres over a circle of latitude form a torus.
+    Latitude,
+}
+
+/// How normals are generated for a [`HopfSurface`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Default, Debug, Clone)]
+pub enum HopfShading {
+    /// One normal per triangle, vertices are duplicated.
+    #[default]
+    Flat,
+    /// Normals averaged over the triangles sharing a vertex.
+    Smooth,
+}
+
+// A grid, with the fibre coordinates [lat, lon, alpha] of each vertex.
+pub(crate) type FibreGrid = (Grid, Vec<[f32; 3]>);
+
+// Sides of each tube, see [`HopfSurface::tube_radius`].
+const TUBE_SIDES: u16 = 12;
+
+/// A Hopf surface primitive.
+///
+/// A curve on s2 ( the base curve ) is divided into `n_loops` seed points,
+/// each seed is lifted into a fibre over the `alpha` range.
+///
+/// As a component, the [`HopfPlugin`](crate::HopfPlugin) keeps the entity's
+/// [`Mesh3d`](bevy::prelude::Mesh3d) in sync with the surface parameters.
+///
+/// ```ignore
+/// let handle = meshes.add(HopfSurface {
+///     n_loops: 27,
+///     ..default()
+/// });
+/// ```
+#[derive(Clone, Component, Debug, Reflect)]
+#[reflect(Component, Default, Debug, Clone)]
+pub struct HopfSurface {
+    /// The start of the base curve.
+    #[reflect(remote = SurfacePointDef)]
+    pub line_start: SurfacePoint,
+    /// The end of the base curve.
+    #[reflect(remote = SurfacePointDef)]
+    pub line_end: SurfacePoint,
+    /// The domain of each fibre, must be contained by 0..=4PI.
+    ///
+    /// A partial range produces an open surface.
+    pub alpha: RangeInclusive<f32>,
+    /// Number of points along each loop.
+    pub n_points_per_loop: usize,
+    /// Number of loops woven into the surface.
+    pub n_loops: u16,
+    /// How the seed points are spread along the base curve.
+    pub seeds: SeedDistribution,
+    /// When positive, each loop is drawn as a tube of this radius
+    /// rather than joined into a sheet.
+    pub tube_radius: f32,
+    /// When positive, the sheet is thickened into a closed shell
+    /// this many model units thick, see [`Grid::solidify`].
+    ///
+    /// Tubes are closed already, and ignore it.
+    pub thickness: f32,
+    /// Flat or smooth normals.
+    pub shading: HopfShading,
+    /// Loop sampling options.
+    pub sampling: HopfSampling,
+    /// Rotation of S3 applied before projection, see [`hopf::rotation`].
+    ///
+    /// Unlike a [`Transform`](bevy::prelude::Transform) this turns
+    /// the surface inside out, through infinity.
+    pub rotation: Mat4,
+}
+
+impl Default for HopfSurface {
     fn default() -> Self {
         Self {
             line_start: SurfacePoint {
@@ -54,65 +226,247 @@ impl Default for Hopf {
                 lat: 45_f32.to_radians(),
                 lon: 2_f32 * core::f32::consts::PI,
             },
+            alpha: 0_f32..=F32_4PI,
+            n_points_per_loop: 40,
             n_loops: 10,
+            seeds: SeedDistribution::Linear,
+            tube_radius: 0.0,
+            thickness: 0.0,
+            shading: HopfShading::Flat,
+            sampling: HopfSampling::default(),
+            rotation: Mat4::IDENTITY,
         }
     }
 }
 
-/// A builder used for creating a [`Mesh`] with an [`Sphere`] shape.
+impl HopfSurface {
+    /// The seed points lifted into loops, see [`SeedDistribution`].
+    #[must_use]
+    pub fn seeds(&self) -> Vec<SurfacePoint> {
+        match self.seeds {
+            SeedDistribution::Linear => {
+                hopf::mesh::weave(&self.line_start, &self.line_end, self.n_loops).collect()
+            }
+            SeedDistribution::Geodesic => {
+                hopf::mesh::weave_geodesic(&self.line_start, &self.line_end, self.n_loops).collect()
+            }
+            SeedDistribution::Latitude => {
+                let end = SurfacePoint {
+                    lat: self.line_start.lat,
+                    lon: self.line_start.lon + core::f32::consts::TAU,
+                };
+                hopf::mesh::weave(&self.line_start, &end, self.n_loops)
+                    .map(|sp| SurfacePoint {
+                        lon: sp.lon.rem_euclid(core::f32::consts::TAU),
+                        ..sp
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// The grids making up the surface, with the fibre coordinates of each vertex.
+    ///
+    /// Either a single sheet, or one tube per loop.
+    ///
+    /// # Errors
+    ///
+    /// See [`HopfMeshBuilder::try_construct`].
+    pub(crate) fn fibre_grids(&self) -> Result<Vec<FibreGrid>, HopfMeshError> {
+        self.shape(self.sample_sheet()?)
+    }
+
+    // Every loop sampled and joined into a single sheet.
+    //
+    // This is the expensive step, coarser levels of detail reuse its samples.
+    pub(crate) fn sample_sheet(&self) -> Result<FibreGrid, HopfMeshError> {
+        let line_start = self.line_start;
+        let line_end = self.line_end;
+        let n_points_per_loop = self.n_points_per_loop;
+        let HopfSampling { n_tries, tolerance } = self.sampling;
+        let alpha = self.alpha.clone();
+        if !(0_f32..=F32_4PI).contains(alpha.start())
+            || !(0_f32..=F32_4PI).contains(alpha.end())
+            || alpha.start() > alpha.end()
+        {
+            return Err(HopfMeshError::AlphaError {
+                alpha_start: *alpha.start(),
+                alpha_end: *alpha.end(),
+            });
+        }
+
+        // The seeds are a series of points which will be transformed into fibres.
+        let mut sheet = Grid::new(n_points_per_loop);
+        let mut fibre_store = Vec::new();
+        for sp in self.seeds() {
+            let fibre = Fibre::new(sp, &alpha).with_rotation(self.rotation);
+
+            // Retry with a finer LUT until the loop is evenly sampled.
+            let (points, alphas) = fibre
+                .try_build_uniform(n_points_per_loop, tolerance, n_tries)
+                .map_err(|e| HopfMeshError::from_fibre(&e, sp))?;
+
+            sheet.push_loop(&points);
+            fibre_store.extend(alphas.iter().map(|alpha| [sp.lat, sp.lon, *alpha]));
+        }
+
+        if sheet.n_loops() == 0 {
+            return Err(HopfMeshError::LineError {
+                lines_start: line_start,
+                lines_end: line_end,
+            });
+        }
+
+        Ok((sheet, fibre_store))
+    }
+
+    // Either the sheet itself ( perhaps thickened ), or one tube per loop.
+    pub(crate) fn shape(
+        &self,
+        (sheet, fibre_store): FibreGrid,
+    ) -> Result<Vec<FibreGrid>, HopfMeshError> {
+        let shape = if self.tube_radius > 0_f32 && sheet.n_points_per_loop() >= 2 {
+            (0..sheet.n_loops())
+                .map(|l| {
+                    let loop_range = sheet.index(l, 0)..sheet.index(l + 1, 0);
+                    let tube = hopf::mesh::tube(
+                        &sheet.vertices[loop_range.clone()],
+                        self.tube_radius,
+                        TUBE_SIDES,
+                    );
+                    // Each ring shares the fibre coordinates of its centre.
+                    let fibres = fibre_store[loop_range]
+                        .iter()
+                        .flat_map(|f| core::iter::repeat_n(*f, tube.n_points_per_loop()))
+                        .collect::<Vec<_>>();
+                    (tube, fibres)
+                })
+                .collect::<Vec<_>>()
+        } else if self.thickness > 0_f32 {
+            // Every layer and wall shares the fibre coordinates of the sheet.
+            let shell = sheet.solidify(self.thickness).map_err(|e| match e {
+                SolidifyError::ZeroNormal(i) => {
+                    let [lat, lon, alpha] = fibre_store[i];
+                    HopfMeshError::NormalError {
+                        sp: SurfacePoint { lat, lon },
+                        alpha,
+                    }
+                }
+            })?;
+            shell
+                .into_iter()
+                .map(|(grid, sources)| {
+                    let fibres = sources.iter().map(|i| fibre_store[*i]).collect();
+                    (grid, fibres)
+                })
+                .collect()
+        } else {
+            vec![(sheet, fibre_store)]
+        };
+        Ok(shape)
+    }
+
+    /// A low resolution version of the surface.
+    ///
+    /// The loop and point counts are divided by `divisor`,
+    /// but never fall below two ( unless already smaller ).
+    #[must_use]
+    pub fn preview(&self, divisor: u16) -> Self {
+        let divisor = divisor.max(1);
+        Self {
+            n_loops: (self.n_loops / divisor).max(2).min(self.n_loops),
+            n_points_per_loop: (self.n_points_per_loop / usize::from(divisor))
+                .max(2)
+                .min(self.n_points_per_loop),
+            ..self.clone()
+        }
+    }
+}
+
+impl Primitive3d for HopfSurface {}
+
+/// A builder used for creating a [`Mesh`] with an [`HopfSurface`] shape.
 // #[derive(Clone, Copy, Debug, Default, Reflect)]
 // #[reflect(Default, Debug, Clone)]
 #[derive(Clone, Debug)]
 pub struct HopfMeshBuilder {
-    /// Deduping mechanism.
-    pub vertex_store: HashMap<Vertex, u16>,
+    /// Vertices stored loop by loop, as in [`Grid`].
+    pub vertex_buffer: Vec<Vec3>,
     /// [1, 2, 3, 4, 5, 6] implies two triangles (1,2,3) and (4,5,6)
     pub triangle_store: Indices,
     /// Per vertex UVs.
     pub uv_store: Vec<[f32; 2]>,
-    /// Number of tries when building a individual loop.
-    pub n_tries: u16,
+    /// Per vertex fibre coordinates, see [`ATTRIBUTE_HOPF_FIBRE`].
+    pub fibre_store: Vec<[f32; 3]>,
 
-    // The last [`Hopf`] shape constructed.
-    hopf: Hopf,
-    next_index: u16,
+    // The last [`HopfSurface`] shape constructed.
+    surface: HopfSurface,
 }
 
 impl HopfMeshBuilder {
-    /// If the point has been seen before it will be deduplicated
-    /// and a exiting vertex buffer index will be returned.
-    pub fn add_vertex(&mut self, p: &Vertex) -> u16 {
-        if let Some(index) = self.vertex_store.get(p) {
-            *index
-        } else {
-            // first time seeing this points
-            // add it to buffer and the store.
-            let index = self.next_index;
-            self.vertex_store.insert(*p, index);
-            // self.vertex_buffer
-            //     .push(Vec3::new(p.0.x as f32, p.0.y as f32, p.0.z as f32));
-            // Could scale a x, y value into  uv space.
-            self.uv_store.push([0.5, 0.5]); // Placeholder for UVs
-            self.next_index += 1;
-            index
-        }
-    }
-
     /// Add a triangle to the mesh.
-    /// The points will be de-duped and normals computed.
-    pub fn add_triangle(&mut self, p0: &Vertex, p1: &Vertex, p2: &Vertex) {
-        let i0 = self.add_vertex(p0);
-        let i1 = self.add_vertex(p1);
-        let i2 = self.add_vertex(p2);
+    ///
+    /// NB. A u16 index buffer is promoted to u32 when an index exceeds `u16::MAX`.
+    pub fn add_triangle(&mut self, i0: u32, i1: u32, i2: u32) {
         // Push the triangle ( anti-clockwise winding order ).
-        self.triangle_store.push(i0.into());
-        self.triangle_store.push(i1.into());
-        self.triangle_store.push(i2.into());
+        self.triangle_store.extend([i0, i1, i2]);
+    }
+
+    /// Optional pass merging vertices which lie within `tolerance` of each other.
+    ///
+    /// The grid topology does not need this, but it allows
+    /// the seam of a closed surface to be joined.
+    ///
+    /// # Panics
+    ///   When `tolerance` is not a positive number.
+    #[must_use]
+    pub fn weld(mut self, tolerance: f32) -> Self {
+        let vertices = self
+            .vertex_buffer
+            .iter()
+            .map(|v| Vertex(*v))
+            .collect::<Vec<_>>();
+        let (welded, remap) = weld(&vertices, tolerance);
+
+        let mut uv_store = vec![[0_f32; 2]; welded.len()];
+        let mut fibre_store = vec![[0_f32; 3]; welded.len()];
+        // Keep the uv of the first vertex in each cluster.
+        for (old, new) in remap.iter().enumerate().rev() {
+            uv_store[*new] = self.uv_store[old];
+            fibre_store[*new] = self.fibre_store[old];
+        }
+
+        self.triangle_store = match self.triangle_store {
+            Indices::U16(indices) => Indices::U16(
+                indices
+                    .into_iter()
+                    .map(|i| {
+                        u16::try_from(remap[usize::from(i)])
+                            .expect("welding cannot increase the vertex count")
+                    })
+                    .collect(),
+            ),
+            Indices::U32(indices) => Indices::U32(
+                indices
+                    .into_iter()
+                    .map(|i| {
+                        u32::try_from(remap[i as usize])
+                            .expect("welding cannot increase the vertex count")
+                    })
+                    .collect(),
+            ),
+        };
+        self.vertex_buffer = welded.into_iter().map(Vec3::from).collect();
+        self.uv_store = uv_store;
+        self.fibre_store = fibre_store;
+        self
     }
 }
 
 impl HopfMeshBuilder {
     /// Creates a new [`HopfMeshBuilder`].
+    ///
+    /// Fibres span the full 0..=4PI range, see [`HopfSurface`] for other options.
     #[must_use = "Not using the returned, is the same a doing nothing at all."]
     #[inline]
     pub fn new(
@@ -121,23 +475,44 @@ impl HopfMeshBuilder {
         n_loops: u16,
         n_tries: u16,
     ) -> Self {
-        Self {
-            hopf: Hopf {
-                line_start: *line_start,
-                line_end: *line_end,
-                n_loops,
+        HopfSurface {
+            line_start: *line_start,
+            line_end: *line_end,
+            n_loops,
+            sampling: HopfSampling {
+                n_tries,
+                ..HopfSampling::default()
             },
-            // Unlike Wavefront OBJ files indexed start at zero
-            next_index: 0,
-            vertex_store: HashMap::default(),
-            triangle_store: Indices::U16(Vec::new()),
-            uv_store: Vec::new(),
-            n_tries,
+            ..HopfSurface::default()
         }
+        .mesh()
+    }
+
+    /// Sets the target accuracy of each loop.
+    #[must_use]
+    pub const fn with_tolerance(mut self, tolerance: f32) -> Self {
+        self.surface.sampling.tolerance = tolerance;
+        self
+    }
+
+    /// The surface being built.
+    #[must_use]
+    pub const fn surface(&self) -> &HopfSurface {
+        &self.surface
     }
 
     /// Creates an hopf mesh with N points per loop
     ///
+    /// # Errors
+    ///
+    /// See [`HopfMeshBuilder::try_construct`].
+    pub fn construct<const N_POINTS_PER_LOOP: usize>(mut self) -> Result<Self, HopfMeshError> {
+        self.surface.n_points_per_loop = N_POINTS_PER_LOOP;
+        self.try_construct()
+    }
+
+    /// Creates an hopf mesh as specified by the [`HopfSurface`].
+    ///
     /// This logic could be folded into `HopfBuilder::build()` but build cannot fail.
     /// and I want better error reporting.
     ///
@@ -145,48 +520,57 @@ impl HopfMeshBuilder {
     ///
     /// `HopfMeshError::LineError` if  `line_start` and `line_end` are identical.
     ///
-    /// `HopfMeshError::NRetriesExceeded` if any loop cannot be constructed.
-    pub fn construct<const N_POINTS_PER_LOOP: usize>(mut self) -> Result<Self, HopfMeshError> {
-        // weave is a series of seed points which will be transformed into fibres.
-        let line_start = self.hopf.line_start;
-        let line_end = self.hopf.line_end;
-        let n_loops = self.hopf.n_loops;
-        let mut weave = hopf::mesh::weave(&line_start, &line_end, n_loops);
-
-        let sp_initial = weave.next().ok_or(HopfMeshError::LineError {
-            lines_start: line_start,
-            lines_end: line_end,
-        })?;
-
-        let alpha = 0_f32..=F32_4PI;
-        let fibre_last = Fibre::new(sp_initial, &alpha);
-
-        let (mut points_last, _alphas) = fibre_last.build_uniform::<N_POINTS_PER_LOOP>();
-
-        for sp in weave {
-            let alpha = 0_f32..=F32_4PI;
-            let fibre = Fibre::new(sp, &alpha);
-
-            let (points, _alphas) = fibre.build_uniform::<N_POINTS_PER_LOOP>();
-
-            //  0 - 3
-            //  | / |
-            //  |/  |
-            //  1 --2
-            //
-            // Given a quad ( points 0, 1, 2, 3 )
-            // form triangles (0,1,3) and (1,2,3)
-            // add triangles will de-dupe points and compute normals.
-            for i in 1..N_POINTS_PER_LOOP {
-                let p0 = &points_last[i - 1];
-                let p1 = &points_last[i];
-                let p2 = &points[i];
-                let p3 = &points[i - 1];
-                self.add_triangle(&p0.clone(), &p1.clone(), &p3.clone());
-                self.add_triangle(&p1.clone(), &p2.clone(), &p3.clone());
-            }
+    /// `HopfMeshError::AlphaError` if `alpha` is not contained by 0..=4PI.
+    ///
+    /// `HopfMeshError::NRetriesExceeded` if any loop cannot be sampled to within
+    /// `tolerance` using `n_tries`.
+    ///
+    /// `HopfMeshError::PoleError` if a loop passes through the projection pole.
+    ///
+    /// `HopfMeshError::IndexOverflow` if the mesh has more vertices than can be indexed by a u32.
+    pub fn try_construct(self) -> Result<Self, HopfMeshError> {
+        let grids = self.surface.fibre_grids()?;
+        self.with_grids(grids)
+    }
+
+    // Fills the buffers from prepared grids.
+    #[allow(clippy::cast_precision_loss)]
+    pub(crate) fn with_grids(mut self, grids: Vec<FibreGrid>) -> Result<Self, HopfMeshError> {
+        // Small meshes use the more compact u16 index buffer.
+        let n_vertices = grids.iter().map(|(grid, _)| grid.vertices.len()).sum();
+        self.triangle_store = if n_vertices <= usize::from(u16::MAX) + 1 {
+            Indices::U16(Vec::new())
+        } else if u32::try_from(n_vertices - 1).is_ok() {
+            Indices::U32(Vec::new())
+        } else {
+            return Err(HopfMeshError::IndexOverflow { n_vertices });
+        };
+
+        self.vertex_buffer.clear();
+        self.uv_store.clear();
+        self.fibre_store.clear();
+        for (grid, fibres) in grids {
+            // Unlike Wavefront OBJ files indexed start at zero
+            let offset = self.vertex_buffer.len();
+            self.vertex_buffer
+                .extend(grid.vertices.iter().map(|v| Vec3::from(*v)));
+            self.fibre_store.extend(fibres);
+
+            // u runs along each loop ( around a tube ), v across the loops.
+            let n_points = grid.n_points_per_loop();
+            let u_max = (n_points.max(2) - 1) as f32;
+            let v_max = (grid.n_loops().max(2) - 1) as f32;
+            self.uv_store.extend((0..grid.n_loops()).flat_map(|l| {
+                let v = l as f32 / v_max;
+                (0..n_points).map(move |i| [i as f32 / u_max, v])
+            }));
 
-            points_last = points;
+            for triangle in grid.triangles() {
+                // Checked above, every index fits into a u32.
+                #[allow(clippy::cast_possible_truncation)]
+                let [i0, i1, i2] = triangle.map(|i| (i + offset) as u32);
+                self.add_triangle(i0, i1, i2);
+            }
         }
 
         Ok(self)
@@ -195,56 +579,265 @@ impl HopfMeshBuilder {
 
 impl MeshBuilder for HopfMeshBuilder {
     /// Builds a [`Mesh`] according to the configuration in `self`.
+    ///
+    /// The surface is constructed first, if that has not already been done.
+    /// As build cannot fail, a construction error is logged and an empty mesh returned.
     fn build(&self) -> Mesh {
-        // Construct a vertex buffer from our deduplicating hash structure
-        //
-        // Three loop over each vertex!  - Is there are better way?
-        // 1) Conversion of hash into a vector
-        // 2) Sorting the vector
-        // 3) Downcasting into DVec3 into Vec3.
-        let mut keyed_vertex_buffer = self.vertex_store.iter().collect::<Vec<_>>();
-        keyed_vertex_buffer.sort_by(|a, b| a.1.cmp(b.1));
-
-        let vertex_buffer: Vec<Vec3> = keyed_vertex_buffer
-            .iter()
-            .map(|(point, _)| (**point).into())
-            .collect();
+        if self.vertex_buffer.is_empty() {
+            return match self.clone().try_construct() {
+                Ok(builder) if !builder.vertex_buffer.is_empty() => builder.build(),
+                Ok(_) => Mesh::new(
+                    PrimitiveTopology::TriangleList,
+                    RenderAssetUsages::default(),
+                ),
+                Err(e) => {
+                    warn!("{e}");
+                    Mesh::new(
+                        PrimitiveTopology::TriangleList,
+                        RenderAssetUsages::default(),
+                    )
+                }
+            };
+        }
 
         let mut mesh = Mesh::new(
             PrimitiveTopology::TriangleList,
             RenderAssetUsages::default(),
         )
-        // .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, self.uv_store.clone())
-        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, vertex_buffer)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, self.vertex_buffer.clone())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, self.uv_store.clone())
+        .with_inserted_attribute(ATTRIBUTE_HOPF_FIBRE, self.fibre_store.clone())
         .with_inserted_indices(self.triangle_store.clone());
 
-        mesh.duplicate_vertices();
-        mesh.compute_flat_normals();
-
-        // To get a broken version of smaooth normals.
-        // Remove duplicate_verticies() (    above )  and add this line
-        // mesh.compute_smooth_normals();
+        match self.surface.shading {
+            HopfShading::Flat => {
+                mesh.duplicate_vertices();
+                mesh.compute_flat_normals();
+            }
+            // NB. The seams are not welded, see [`HopfMeshBuilder::weld`].
+            HopfShading::Smooth => mesh.compute_smooth_normals(),
+        }
         mesh
     }
 }
 
-impl Meshable for Hopf {
+impl Meshable for HopfSurface {
     type Output = HopfMeshBuilder;
 
     fn mesh(&self) -> Self::Output {
         HopfMeshBuilder {
-            hopf: self.clone(),
-            next_index: 0,
-            vertex_store: HashMap::default(),
+            surface: self.clone(),
+            vertex_buffer: Vec::new(),
             triangle_store: Indices::U16(Vec::new()),
             uv_store: Vec::new(),
-            n_tries: 2000,
+            fibre_store: Vec::new(),
         }
     }
 }
 
-impl From<Hopf> for Mesh {
-    fn from(hopf: Hopf) -> Self {
-        hopf.mesh().build()
+impl From<HopfSurface> for Mesh {
+    fn from(surface: HopfSurface) -> Self {
+        surface.mesh().build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_mesh_uses_u16_indices() {
+        let builder = HopfMeshBuilder::new(
+            &SurfacePoint {
+                lat: 10_f32.to_radians(),
+                lon: 0_f32,
+            },
+            &SurfacePoint {
+                lat: 10_f32.to_radians(),
+                lon: 270_f32.to_radians(),
+            },
+            27,
+            2000,
+        )
+        .construct::<40>()
+        .expect("Failed to construct mesh");
+
+        assert!(matches!(builder.triangle_store, Indices::U16(_)));
+        assert_eq!(builder.vertex_buffer.len(), 27 * 40);
+    }
+
+    #[test]
+    fn large_mesh_promoted_to_u32_indices() {
+        // 300 loops x 400 points is well beyond 65,535 vertices.
+        let builder = HopfMeshBuilder::new(
+            &SurfacePoint {
+                lat: 10_f32.to_radians(),
+                lon: 0_f32,
+            },
+            &SurfacePoint {
+                lat: 10_f32.to_radians(),
+                lon: 270_f32.to_radians(),
+            },
+            300,
+            2000,
+        )
+        .construct::<400>()
+        .expect("Failed to construct mesh");
+
+        let n_vertices = builder.vertex_buffer.len();
+        assert_eq!(n_vertices, 300 * 400);
+        assert!(matches!(builder.triangle_store, Indices::U32(_)));
+        assert_eq!(builder.triangle_store.iter().max(), Some(n_vertices - 1));
+    }
+
+    #[test]
+    fn smooth_shading_shares_vertices() {
+        let surface = HopfSurface {
+            n_points_per_loop: 20,
+            n_loops: 5,
+            shading: HopfShading::Smooth,
+            ..HopfSurface::default()
+        };
+        let mesh = Mesh::from(surface);
+        assert_eq!(mesh.count_vertices(), 5 * 20);
+        assert!(mesh.attribute(Mesh::ATTRIBUTE_NORMAL).is_some());
+    }
+
+    #[test]
+    fn tubes_replace_the_sheet() {
+        let builder = HopfSurface {
+            n_points_per_loop: 20,
+            n_loops: 5,
+            tube_radius: 0.05,
+            ..HopfSurface::default()
+        }
+        .mesh()
+        .try_construct()
+        .expect("Failed to construct mesh");
+
+        let ring = usize::from(TUBE_SIDES) + 1;
+        assert_eq!(builder.vertex_buffer.len(), 5 * 20 * ring);
+        assert_eq!(builder.fibre_store.len(), builder.vertex_buffer.len());
+        // Two triangles per quad, each tube is its own grid.
+        assert_eq!(builder.triangle_store.len(), 5 * 19 * (ring - 1) * 2 * 3);
+    }
+
+    #[test]
+    fn thickness_closes_the_sheet() {
+        let builder = HopfSurface {
+            line_end: SurfacePoint {
+                lat: 45_f32.to_radians(),
+                lon: core::f32::consts::PI,
+            },
+            n_points_per_loop: 20,
+            n_loops: 5,
+            thickness: 0.02,
+            ..HopfSurface::default()
+        }
+        .mesh()
+        .try_construct()
+        .expect("Failed to construct mesh");
+
+        // Each fibre is a closed loop, so only the first and last loops need walls.
+        assert_eq!(builder.vertex_buffer.len(), 2 * 5 * 20 + 2 * 2 * 20);
+        assert_eq!(builder.fibre_store.len(), builder.vertex_buffer.len());
+        assert_eq!(builder.triangle_store.len(), (2 * 4 + 2) * 19 * 2 * 3);
+    }
+
+    #[test]
+    fn latitude_seeds_form_a_full_circle() {
+        let surface = HopfSurface {
+            n_loops: 8,
+            seeds: SeedDistribution::Latitude,
+            ..HopfSurface::default()
+        };
+        let seeds = surface.seeds();
+        assert_eq!(seeds.len(), 8);
+        for (i, sp) in seeds.iter().enumerate() {
+            assert!((sp.lat - surface.line_start.lat).abs() < 1e-6);
+            let expected = core::f32::consts::FRAC_PI_4 * f32::from(u8::try_from(i).unwrap());
+            assert!((sp.lon - expected).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn partial_surface() {
+        let surface = HopfSurface {
+            alpha: 0_f32..=core::f32::consts::PI,
+            n_points_per_loop: 20,
+            n_loops: 5,
+            ..HopfSurface::default()
+        };
+        let mesh = Mesh::from(surface);
+        // Flat shading duplicates every vertex of each triangle.
+        assert_eq!(mesh.count_vertices(), 4 * 19 * 2 * 3);
+
+        let surface = HopfSurface {
+            alpha: 0_f32..=5_f32 * core::f32::consts::PI,
+            ..HopfSurface::default()
+        };
+        assert!(matches!(
+            surface.mesh().try_construct(),
+            Err(HopfMeshError::AlphaError { .. })
+        ));
+    }
+
+    #[test]
+    fn retries_exceeded_reports_surface_point() {
+        let line_start = SurfacePoint {
+            lat: 10_f32.to_radians(),
+            lon: 0_f32,
+        };
+        let result = HopfMeshBuilder::new(
+            &line_start,
+            &SurfacePoint {
+                lat: 10_f32.to_radians(),
+                lon: 270_f32.to_radians(),
+            },
+            27,
+            2,
+        )
+        // Perfectly even spacing is unreachable.
+        .with_tolerance(0_f32)
+        .construct::<40>();
+
+        match result {
+            Err(HopfMeshError::NRetriesExceeded { n_tries, sp }) => {
+                assert_eq!(n_tries, 2);
+                // The first loop fails.
+                assert!((sp.lat - line_start.lat).abs() < f32::EPSILON);
+                assert!((sp.lon - line_start.lon).abs() < f32::EPSILON);
+            }
+            _ => panic!("expected NRetriesExceeded"),
+        }
+    }
+
+    #[test]
+    fn pole_crossing_reports_the_loop() {
+        use hopf::rotation::Plane;
+        use hopf::rotation::plane_rotation;
+
+        // Rotated so the first loop runs through the projection pole.
+        let surface = HopfSurface {
+            line_start: SurfacePoint {
+                lat: 0_f32,
+                lon: 0_f32,
+            },
+            line_end: SurfacePoint {
+                lat: 0_f32,
+                lon: 90_f32.to_radians(),
+            },
+            n_loops: 2,
+            n_points_per_loop: 40,
+            rotation: plane_rotation(Plane::YW, core::f32::consts::FRAC_PI_4),
+            ..HopfSurface::default()
+        };
+        match surface.fibre_grids() {
+            Err(HopfMeshError::PoleError { sp }) => {
+                assert!(sp.lat.abs() < f32::EPSILON);
+                assert!(sp.lon.abs() < f32::EPSILON);
+            }
+            _ => panic!("expected PoleError"),
+        }
     }
 }
@@ -9,18 +9,101 @@
 #![warn(missing_docs)]
 #![allow(clippy::many_single_char_names)]
 
+/// An orbit camera for inspecting Hopf surfaces.
+pub mod camera;
+
+/// Writing Hopf surfaces, and their parameters, to file.
+pub mod export;
+
+/// A single Hopf fibre primitive.
+pub mod fibre;
+
+/// Animated flow along fibres.
+pub mod flow;
+
 /// A struct and methods for generating a Hopf mesh.
 pub mod hopf;
 
+/// Levels of detail, for surfaces far from the camera.
+pub mod lod;
+
+/// A ui panel editing the parameters of a Hopf surface.
+pub mod panel;
+
+/// Mapping points on a Hopf mesh back onto their fibre.
+pub mod pick;
+
+/// Systems keeping meshes in sync with their Hopf surface.
+pub mod rebuild;
+
 /// A struct and methods for generating a Hopf fibration.
-#[derive(Debug)]
-pub struct HopfPlugin;
+///
+/// Entities holding a [`HopfSurface`] or a [`HopfFibre`] have their
+/// [`Mesh3d`](bevy::prelude::Mesh3d) regenerated whenever the parameters change.
+///
+/// By default meshes are built asynchronously, see [`HopfMeshSettings`].
+/// Sending an [`ExportHopfScene`] message writes the surfaces and fibres to file.
+#[derive(Debug, Default)]
+pub struct HopfPlugin {
+    /// Drive every [`HopfOrbitCamera`] from the mouse and keyboard.
+    pub orbit_camera: bool,
+}
 
 use bevy::app::App;
 use bevy::app::Plugin;
+use bevy::app::Update;
+use bevy::ecs::schedule::IntoScheduleConfigs;
+use bevy::pbr::StandardMaterial;
+
+use crate::camera::BlocksCameraDrag;
+use crate::camera::HopfOrbitCamera;
+use crate::camera::HopfSelected;
+use crate::camera::OrbitDrag;
+use crate::camera::apply_orbit_camera;
+use crate::camera::frame_selected;
+use crate::camera::orbit_camera_input;
+
+use crate::export::ExportHopfScene;
+use crate::export::export_hopf_scene;
+use crate::fibre::HopfFibre;
+use crate::hopf::HopfSurface;
+use crate::lod::HopfLod;
+use crate::lod::HopfLodLevel;
+use crate::lod::copy_lod_material;
+use crate::lod::sync_hopf_lod;
+use crate::rebuild::HopfMeshSettings;
+use crate::rebuild::poll_hopf_mesh_tasks;
+use crate::rebuild::rebuild_hopf_fibres;
+use crate::rebuild::rebuild_hopf_meshes;
 
 impl Plugin for HopfPlugin {
-    fn build(&self, _app: &mut App) {
-        // add things to your app here
+    fn build(&self, app: &mut App) {
+        app.register_type::<HopfSurface>()
+            .register_type::<HopfFibre>()
+            .register_type::<HopfMeshSettings>()
+            .register_type::<HopfLod>()
+            .register_type::<HopfLodLevel>()
+            .init_resource::<HopfMeshSettings>()
+            .add_message::<ExportHopfScene>()
+            .add_systems(
+                Update,
+                (
+                    (rebuild_hopf_meshes, poll_hopf_mesh_tasks).chain(),
+                    rebuild_hopf_fibres,
+                    (sync_hopf_lod, copy_lod_material::<StandardMaterial>),
+                    export_hopf_scene,
+                ),
+            );
+
+        if self.orbit_camera {
+            app.register_type::<HopfOrbitCamera>()
+                .register_type::<BlocksCameraDrag>()
+                .register_type::<HopfSelected>()
+                .init_resource::<OrbitDrag>()
+                .add_systems(
+                    Update,
+                    (orbit_camera_input, frame_selected, apply_orbit_camera).chain(),
+                );
+        }
     }
 }
@@ -0,0 +1,254 @@
+use bevy::asset::Assets;
+use bevy::camera::visibility::VisibilityRange;
+use bevy::ecs::component::Component;
+use bevy::ecs::entity::Entity;
+use bevy::ecs::hierarchy::ChildOf;
+use bevy::ecs::lifecycle::RemovedComponents;
+use bevy::ecs::query::With;
+use bevy::ecs::reflect::ReflectComponent;
+use bevy::ecs::system::Commands;
+use bevy::ecs::system::Query;
+use bevy::pbr::Material;
+use bevy::pbr::MeshMaterial3d;
+use bevy::prelude::Mesh3d;
+use bevy::reflect::Reflect;
+use bevy::reflect::std_traits::ReflectDefault;
+use bevy_mesh::Mesh;
+use bevy_mesh::MeshBuilder;
+use bevy_mesh::Meshable;
+
+use crate::hopf::HopfMeshError;
+use crate::hopf::HopfSurface;
+use crate::rebuild::swap_mesh;
+
+/// Coarser versions of a [`HopfSurface`], shown as the camera moves away.
+///
+/// Each level halves the loops and points of the one before, the fibres
+/// are sampled once and shared by every level. Levels crossfade using
+/// [`VisibilityRange`], coarser levels are spawned as [`HopfLodLevel`] children.
+///
+/// Children copy the parent's [`StandardMaterial`](bevy::pbr::StandardMaterial),
+/// add [`copy_lod_material`] for other materials.
+#[derive(Clone, Component, Debug, Reflect)]
+#[reflect(Component, Default, Debug, Clone)]
+pub struct HopfLod {
+    /// Camera distances at which each coarser level takes over, increasing.
+    pub distances: Vec<f32>,
+    /// Width of the crossfade between neighbouring levels, zero switches abruptly.
+    pub fade: f32,
+}
+
+impl Default for HopfLod {
+    fn default() -> Self {
+        Self {
+            distances: vec![20.0, 40.0, 80.0],
+            fade: 4.0,
+        }
+    }
+}
+
+impl HopfLod {
+    /// The number of coarser levels.
+    #[must_use]
+    pub fn n_coarse(&self) -> usize {
+        self.distances.len()
+    }
+
+    /// The visibility range of each level, starting with the full surface.
+    ///
+    /// The end margin of one level matches the start margin of the next,
+    /// so neighbouring levels crossfade rather than pop.
+    #[must_use]
+    pub fn ranges(&self) -> Vec<VisibilityRange> {
+        let half = self.fade.max(0_f32) / 2_f32;
+        let margins = core::iter::once(0_f32..0_f32)
+            .chain(
+                self.distances
+                    .iter()
+                    .map(|d| (d - half).max(0_f32)..d + half),
+            )
+            .chain(core::iter::once(f32::MAX..f32::MAX))
+            .collect::<Vec<_>>();
+        margins
+            .windows(2)
+            .map(|pair| VisibilityRange {
+                start_margin: pair[0].clone(),
+                end_margin: pair[1].clone(),
+                use_aabb: false,
+            })
+            .collect()
+    }
+}
+
+/// A coarser level of the [`HopfSurface`] on the parent entity, starting at 1.
+///
+/// Spawned and despawned along with the parent's [`HopfLod`].
+#[derive(Clone, Component, Copy, Debug, PartialEq, Eq, Reflect)]
+#[reflect(Component, Debug, Clone)]
+pub struct HopfLodLevel(pub usize);
+
+/// Builds the full surface, followed by `n_coarse` coarser levels.
+///
+/// Level `k` keeps every 2^k th loop and point of the full surface
+/// ( the first and last are always kept ), so no fibre is sampled twice.
+///
+/// # Errors
+///
+/// See [`HopfMeshBuilder::try_construct`](crate::hopf::HopfMeshBuilder::try_construct).
+pub fn build_lod_meshes(
+    surface: &HopfSurface,
+    n_coarse: usize,
+) -> Result<Vec<Mesh>, HopfMeshError> {
+    let (sheet, fibres) = surface.sample_sheet()?;
+    let mut meshes = Vec::with_capacity(n_coarse + 1);
+    for level in 0..=n_coarse {
+        // Once the step passes the grid size, only the first and last remain.
+        let step = u32::try_from(level)
+            .ok()
+            .and_then(|level| 1_usize.checked_shl(level))
+            .unwrap_or(usize::MAX);
+        let (grid, kept) = sheet.subsample(step, step);
+        let fibres = kept.iter().map(|i| fibres[*i]).collect();
+        let builder = surface.mesh().with_grids(surface.shape((grid, fibres))?)?;
+        meshes.push(builder.build());
+    }
+    Ok(meshes)
+}
+
+/// Swaps in the coarser levels of a surface, `coarse[0]` being level 1.
+///
+/// Missing level entities are spawned, surplus ones despawned.
+pub fn swap_lod_levels(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    entity: Entity,
+    levels: &Query<(Entity, &HopfLodLevel, &ChildOf, Option<&Mesh3d>)>,
+    coarse: Vec<Mesh>,
+) {
+    let mut coarse = coarse.into_iter().map(Some).collect::<Vec<_>>();
+    for (child, HopfLodLevel(level), child_of, mesh3d) in levels {
+        if child_of.parent() != entity {
+            continue;
+        }
+        match level
+            .checked_sub(1)
+            .and_then(|i| coarse.get_mut(i))
+            .and_then(Option::take)
+        {
+            Some(mesh) => swap_mesh(commands, meshes, child, mesh3d, mesh),
+            None => commands.entity(child).despawn(),
+        }
+    }
+    for (i, mesh) in coarse.into_iter().enumerate() {
+        if let Some(mesh) = mesh {
+            commands.spawn((
+                HopfLodLevel(i + 1),
+                Mesh3d(meshes.add(mesh)),
+                ChildOf(entity),
+            ));
+        }
+    }
+}
+
+/// Keeps the [`VisibilityRange`] of each level in step with its [`HopfLod`].
+///
+/// When the [`HopfLod`] is removed the levels are despawned,
+/// and the full surface is shown at every distance.
+#[allow(clippy::needless_pass_by_value)]
+pub fn sync_hopf_lod(
+    mut commands: Commands,
+    surfaces: Query<(Entity, &HopfLod, Option<&VisibilityRange>), With<HopfSurface>>,
+    levels: Query<(Entity, &HopfLodLevel, &ChildOf, Option<&VisibilityRange>)>,
+    mut removed: RemovedComponents<HopfLod>,
+) {
+    for (entity, lod, range) in &surfaces {
+        let ranges = lod.ranges();
+        if range != Some(&ranges[0]) {
+            commands.entity(entity).insert(ranges[0].clone());
+        }
+        for (child, HopfLodLevel(level), child_of, range) in &levels {
+            if child_of.parent() != entity {
+                continue;
+            }
+            if let Some(wanted) = ranges.get(*level)
+                && range != Some(wanted)
+            {
+                commands.entity(child).insert(wanted.clone());
+            }
+        }
+    }
+
+    for entity in removed.read() {
+        if surfaces.contains(entity) {
+            continue;
+        }
+        if let Ok(mut parent) = commands.get_entity(entity) {
+            parent.remove::<VisibilityRange>();
+        }
+        for (child, _, child_of, _) in &levels {
+            if child_of.parent() == entity {
+                commands.entity(child).despawn();
+            }
+        }
+    }
+}
+
+/// Copies the parent's material onto each [`HopfLodLevel`].
+#[allow(clippy::needless_pass_by_value, clippy::type_complexity)]
+pub fn copy_lod_material<M: Material>(
+    mut commands: Commands,
+    parents: Query<&MeshMaterial3d<M>, With<HopfLod>>,
+    levels: Query<(Entity, &ChildOf, Option<&MeshMaterial3d<M>>), With<HopfLodLevel>>,
+) {
+    for (child, child_of, material) in &levels {
+        let Ok(wanted) = parents.get(child_of.parent()) else {
+            continue;
+        };
+        if material != Some(wanted) {
+            commands.entity(child).insert(wanted.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levels_reuse_the_full_samples() {
+        let surface = HopfSurface {
+            n_loops: 9,
+            n_points_per_loop: 17,
+            ..HopfSurface::default()
+        };
+        let meshes = build_lod_meshes(&surface, 2).unwrap();
+        // Flat shading, 6 vertices per quad.
+        let quads = meshes
+            .iter()
+            .map(|mesh| mesh.count_vertices() / 6)
+            .collect::<Vec<_>>();
+        assert_eq!(quads, vec![8 * 16, 4 * 8, 2 * 4]);
+
+        // The full level matches a plain build.
+        let full = Mesh::from(surface);
+        assert_eq!(
+            meshes[0].attribute(Mesh::ATTRIBUTE_POSITION).unwrap().len(),
+            full.attribute(Mesh::ATTRIBUTE_POSITION).unwrap().len()
+        );
+    }
+
+    #[test]
+    fn ranges_crossfade() {
+        let lod = HopfLod {
+            distances: vec![10.0, 30.0],
+            fade: 2.0,
+        };
+        let ranges = lod.ranges();
+        assert_eq!(ranges.len(), 3);
+        assert_eq!(ranges[0].start_margin, 0.0..0.0);
+        assert_eq!(ranges[0].end_margin, 9.0..11.0);
+        assert_eq!(ranges[1].start_margin, ranges[0].end_margin);
+        assert_eq!(ranges[2].start_margin, 29.0..31.0);
+        assert!(!ranges[2].is_culled(1e6));
+    }
+}
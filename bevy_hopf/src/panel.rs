@@ -0,0 +1,433 @@
+use core::ops::RangeInclusive;
+
+use bevy::app::App;
+use bevy::app::Plugin;
+use bevy::app::Update;
+use bevy::color::Color;
+use bevy::ecs::component::Component;
+use bevy::ecs::entity::Entity;
+use bevy::ecs::query::Changed;
+use bevy::ecs::query::Has;
+use bevy::ecs::query::With;
+use bevy::ecs::schedule::IntoScheduleConfigs;
+use bevy::ecs::system::Commands;
+use bevy::ecs::system::Query;
+use bevy::pbr::wireframe::Wireframe;
+use bevy::prelude::ChildSpawnerCommands;
+use bevy::text::TextFont;
+use bevy::ui::AlignItems;
+use bevy::ui::BackgroundColor;
+use bevy::ui::FlexDirection;
+use bevy::ui::Interaction;
+use bevy::ui::JustifyContent;
+use bevy::ui::Node;
+use bevy::ui::PositionType;
+use bevy::ui::RelativeCursorPosition;
+use bevy::ui::UiRect;
+use bevy::ui::percent;
+use bevy::ui::px;
+use bevy::ui::widget::Button;
+use bevy::ui::widget::Text;
+use hopf::F32_4PI;
+
+use crate::camera::BlocksCameraDrag;
+use crate::hopf::HopfShading;
+use crate::hopf::HopfSurface;
+use crate::hopf::SeedDistribution;
+
+/// A [`HopfSurface`] parameter edited by a slider.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SliderParam {
+    /// [`HopfSurface::n_loops`].
+    Loops,
+    /// [`HopfSurface::n_points_per_loop`].
+    PointsPerLoop,
+    /// The start of [`HopfSurface::alpha`].
+    AlphaStart,
+    /// The end of [`HopfSurface::alpha`].
+    AlphaEnd,
+    /// [`HopfSurface::tube_radius`], zero draws a sheet.
+    TubeRadius,
+    /// [`HopfSurface::thickness`], zero leaves the sheet open.
+    Thickness,
+}
+
+impl SliderParam {
+    /// Every slider, in the order shown by the panel.
+    pub const ALL: [Self; 6] = [
+        Self::Loops,
+        Self::PointsPerLoop,
+        Self::AlphaStart,
+        Self::AlphaEnd,
+        Self::TubeRadius,
+        Self::Thickness,
+    ];
+
+    /// The values covered by the slider.
+    #[must_use]
+    pub const fn range(self) -> RangeInclusive<f32> {
+        match self {
+            Self::Loops => 2_f32..=200_f32,
+            Self::PointsPerLoop => 3_f32..=400_f32,
+            Self::AlphaStart | Self::AlphaEnd => 0_f32..=F32_4PI,
+            Self::TubeRadius | Self::Thickness => 0_f32..=0.25,
+        }
+    }
+
+    const fn label(self) -> &'static str {
+        match self {
+            Self::Loops => "Loops",
+            Self::PointsPerLoop => "Points per loop",
+            Self::AlphaStart => "Alpha start",
+            Self::AlphaEnd => "Alpha end",
+            Self::TubeRadius => "Tube radius",
+            Self::Thickness => "Thickness",
+        }
+    }
+
+    /// The current value of the parameter.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn get(self, surface: &HopfSurface) -> f32 {
+        match self {
+            Self::Loops => f32::from(surface.n_loops),
+            Self::PointsPerLoop => surface.n_points_per_loop as f32,
+            Self::AlphaStart => *surface.alpha.start(),
+            Self::AlphaEnd => *surface.alpha.end(),
+            Self::TubeRadius => surface.tube_radius,
+            Self::Thickness => surface.thickness,
+        }
+    }
+
+    /// Sets the parameter, clamped to the slider range.
+    ///
+    /// Counts are rounded, and the alpha range is kept ordered.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn set(self, surface: &mut HopfSurface, value: f32) {
+        let range = self.range();
+        let value = value.clamp(*range.start(), *range.end());
+        match self {
+            Self::Loops => surface.n_loops = value.round() as u16,
+            Self::PointsPerLoop => surface.n_points_per_loop = value.round() as usize,
+            Self::AlphaStart => {
+                surface.alpha = value.min(*surface.alpha.end())..=*surface.alpha.end();
+            }
+            Self::AlphaEnd => {
+                surface.alpha = *surface.alpha.start()..=value.max(*surface.alpha.start());
+            }
+            Self::TubeRadius => surface.tube_radius = value,
+            Self::Thickness => surface.thickness = value,
+        }
+    }
+
+    /// Where the current value sits along the slider, 0..=1.
+    #[must_use]
+    pub fn fraction(self, surface: &HopfSurface) -> f32 {
+        let range = self.range();
+        ((self.get(surface) - range.start()) / (range.end() - range.start())).clamp(0_f32, 1_f32)
+    }
+}
+
+/// A control on a [`HopfPanel`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PanelControl {
+    /// Drag to set a parameter.
+    Slider(SliderParam),
+    /// Switches between flat and smooth shading.
+    Shading,
+    /// Toggles a [`Wireframe`] on the surface.
+    Wireframe,
+    /// Cycles through the [`SeedDistribution`]s.
+    Seeds,
+}
+
+/// The root node of a parameter panel, see [`spawn_hopf_panel`].
+#[derive(Clone, Component, Copy, Debug)]
+pub struct HopfPanel {
+    /// The entity holding the [`HopfSurface`].
+    pub target: Entity,
+}
+
+/// A panel control, or one of its parts, bound to a [`HopfSurface`].
+///
+/// The pressable part also holds an [`Interaction`].
+#[derive(Clone, Component, Copy, Debug)]
+pub struct HopfPanelControl {
+    /// The entity holding the [`HopfSurface`].
+    pub target: Entity,
+    /// What the control edits.
+    pub control: PanelControl,
+}
+
+/// The filled part of a slider track.
+#[derive(Clone, Component, Copy, Debug)]
+pub struct SliderFill;
+
+/// Text describing the value of a control.
+#[derive(Clone, Component, Copy, Debug)]
+pub struct ControlLabel;
+
+/// Keeps every [`HopfPanel`] and its surface in sync.
+///
+/// Separate from [`HopfPlugin`](crate::HopfPlugin) as it requires the ui plugin.
+/// [`PanelControl::Wireframe`] has no visible effect without the
+/// [`WireframePlugin`](bevy::pbr::wireframe::WireframePlugin).
+#[derive(Debug)]
+pub struct HopfPanelPlugin;
+
+impl Plugin for HopfPanelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (drag_panel_sliders, press_panel_buttons, refresh_panel).chain(),
+        );
+    }
+}
+
+const PANEL_BACKGROUND: Color = Color::srgba(0.1, 0.1, 0.12, 0.85);
+const TRACK_COLOR: Color = Color::srgb(0.3, 0.3, 0.35);
+const FILL_COLOR: Color = Color::srgb(0.35, 0.65, 0.95);
+const BUTTON_COLOR: Color = Color::srgb(0.2, 0.2, 0.25);
+const FONT_SIZE: f32 = 14.0;
+
+/// Spawns a panel editing the [`HopfSurface`] on `target`, in the top right corner.
+///
+/// The panel blocks camera drags, see [`BlocksCameraDrag`].
+pub fn spawn_hopf_panel(commands: &mut Commands, target: Entity) -> Entity {
+    commands
+        .spawn((
+            HopfPanel { target },
+            BlocksCameraDrag,
+            Node {
+                position_type: PositionType::Absolute,
+                top: px(12),
+                right: px(12),
+                width: px(240),
+                flex_direction: FlexDirection::Column,
+                row_gap: px(6),
+                padding: UiRect::all(px(10)),
+                ..Node::default()
+            },
+            BackgroundColor(PANEL_BACKGROUND),
+        ))
+        .with_children(|panel| {
+            for param in SliderParam::ALL {
+                spawn_slider(panel, target, param);
+            }
+            for control in [
+                PanelControl::Shading,
+                PanelControl::Wireframe,
+                PanelControl::Seeds,
+            ] {
+                spawn_button(panel, target, control);
+            }
+        })
+        .id()
+}
+
+fn spawn_slider(panel: &mut ChildSpawnerCommands, target: Entity, param: SliderParam) {
+    let control = HopfPanelControl {
+        target,
+        control: PanelControl::Slider(param),
+    };
+    panel.spawn((
+        control,
+        ControlLabel,
+        Text::default(),
+        TextFont::from_font_size(FONT_SIZE),
+    ));
+    panel
+        .spawn((
+            control,
+            Button,
+            RelativeCursorPosition::default(),
+            Node {
+                width: percent(100),
+                height: px(10),
+                ..Node::default()
+            },
+            BackgroundColor(TRACK_COLOR),
+        ))
+        .with_child((
+            control,
+            SliderFill,
+            Node {
+                width: percent(0),
+                height: percent(100),
+                ..Node::default()
+            },
+            BackgroundColor(FILL_COLOR),
+        ));
+}
+
+fn spawn_button(panel: &mut ChildSpawnerCommands, target: Entity, control: PanelControl) {
+    let control = HopfPanelControl { target, control };
+    panel
+        .spawn((
+            control,
+            Button,
+            Node {
+                padding: UiRect::axes(px(8), px(4)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Node::default()
+            },
+            BackgroundColor(BUTTON_COLOR),
+        ))
+        .with_child((
+            control,
+            ControlLabel,
+            Text::default(),
+            TextFont::from_font_size(FONT_SIZE),
+        ));
+}
+
+/// Sets slider parameters from the cursor, while a track is pressed.
+#[allow(clippy::needless_pass_by_value)]
+pub fn drag_panel_sliders(
+    sliders: Query<(&HopfPanelControl, &Interaction, &RelativeCursorPosition)>,
+    mut surfaces: Query<&mut HopfSurface>,
+) {
+    for (control, interaction, cursor) in &sliders {
+        let PanelControl::Slider(param) = control.control else {
+            continue;
+        };
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let (Some(normalized), Ok(mut surface)) =
+            (cursor.normalized, surfaces.get_mut(control.target))
+        else {
+            continue;
+        };
+
+        // Normalized runs from -0.5 to 0.5 across the node.
+        let fraction = (normalized.x + 0.5).clamp(0_f32, 1_f32);
+        let range = param.range();
+        let value = (range.end() - range.start()).mul_add(fraction, *range.start());
+
+        // Only touch the surface on a real change, each change triggers a rebuild.
+        let mut edited = surface.clone();
+        param.set(&mut edited, value);
+        if (param.get(&edited) - param.get(&surface)).abs() > f32::EPSILON {
+            param.set(&mut surface, value);
+        }
+    }
+}
+
+/// Handles the toggle and selector buttons.
+#[allow(clippy::needless_pass_by_value)]
+pub fn press_panel_buttons(
+    buttons: Query<(&HopfPanelControl, &Interaction), Changed<Interaction>>,
+    mut surfaces: Query<(&mut HopfSurface, Has<Wireframe>)>,
+    mut commands: Commands,
+) {
+    for (control, interaction) in &buttons {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let Ok((mut surface, wireframe)) = surfaces.get_mut(control.target) else {
+            continue;
+        };
+        match control.control {
+            PanelControl::Slider(_) => {}
+            PanelControl::Shading => {
+                surface.shading = match surface.shading {
+                    HopfShading::Flat => HopfShading::Smooth,
+                    HopfShading::Smooth => HopfShading::Flat,
+                };
+            }
+            PanelControl::Wireframe => {
+                if wireframe {
+                    commands.entity(control.target).remove::<Wireframe>();
+                } else {
+                    commands.entity(control.target).insert(Wireframe);
+                }
+            }
+            PanelControl::Seeds => {
+                surface.seeds = match surface.seeds {
+                    SeedDistribution::Linear => SeedDistribution::Geodesic,
+                    SeedDistribution::Geodesic => SeedDistribution::Latitude,
+                    SeedDistribution::Latitude => SeedDistribution::Linear,
+                };
+            }
+        }
+    }
+}
+
+/// Shows the surface parameters on the panel.
+///
+/// Reads the surface every frame, so edits made elsewhere are reflected.
+#[allow(clippy::needless_pass_by_value, clippy::type_complexity)]
+pub fn refresh_panel(
+    surfaces: Query<(&HopfSurface, Has<Wireframe>)>,
+    mut fills: Query<(&HopfPanelControl, &mut Node), With<SliderFill>>,
+    mut labels: Query<(&HopfPanelControl, &mut Text), With<ControlLabel>>,
+) {
+    for (control, mut node) in &mut fills {
+        let (PanelControl::Slider(param), Ok((surface, _))) =
+            (control.control, surfaces.get(control.target))
+        else {
+            continue;
+        };
+        let width = percent(param.fraction(surface) * 100_f32);
+        if node.width != width {
+            node.width = width;
+        }
+    }
+
+    for (control, mut text) in &mut labels {
+        let Ok((surface, wireframe)) = surfaces.get(control.target) else {
+            continue;
+        };
+        let description = describe(control.control, surface, wireframe);
+        if text.0 != description {
+            text.0 = description;
+        }
+    }
+}
+
+fn describe(control: PanelControl, surface: &HopfSurface, wireframe: bool) -> String {
+    match control {
+        PanelControl::Slider(
+            param @ (SliderParam::AlphaStart
+            | SliderParam::AlphaEnd
+            | SliderParam::TubeRadius
+            | SliderParam::Thickness),
+        ) => format!("{}: {:.2}", param.label(), param.get(surface)),
+        PanelControl::Slider(param) => format!("{}: {}", param.label(), param.get(surface)),
+        PanelControl::Shading => format!("Shading: {:?}", surface.shading),
+        PanelControl::Wireframe => format!("Wireframe: {}", if wireframe { "on" } else { "off" }),
+        PanelControl::Seeds => format!("Seeds: {:?}", surface.seeds),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sliders_round_and_clamp() {
+        let mut surface = HopfSurface::default();
+        SliderParam::Loops.set(&mut surface, 26.6);
+        assert_eq!(surface.n_loops, 27);
+        SliderParam::PointsPerLoop.set(&mut surface, 1e6);
+        assert_eq!(surface.n_points_per_loop, 400);
+        assert!((SliderParam::PointsPerLoop.fraction(&surface) - 1_f32).abs() < 1e-6);
+        for param in SliderParam::ALL {
+            let value = param.get(&surface);
+            param.set(&mut surface, value);
+            assert!((param.get(&surface) - value).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn alpha_range_stays_ordered() {
+        let mut surface = HopfSurface::default();
+        SliderParam::AlphaEnd.set(&mut surface, 2.0);
+        SliderParam::AlphaStart.set(&mut surface, 3.0);
+        assert_eq!(surface.alpha, 2_f32..=2_f32);
+        SliderParam::AlphaEnd.set(&mut surface, 1.0);
+        assert_eq!(surface.alpha, 2_f32..=2_f32);
+    }
+}
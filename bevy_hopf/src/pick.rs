@@ -0,0 +1,194 @@
+use bevy::prelude::Vec3;
+use bevy_mesh::Indices;
+use bevy_mesh::Mesh;
+use bevy_mesh::MeshVertexAttribute;
+use bevy_mesh::VertexAttributeValues;
+use bevy_mesh::VertexFormat;
+use hopf::sp::SurfacePoint;
+
+/// Per vertex fibre coordinates `[lat, lon, alpha]` of a Hopf mesh.
+///
+/// The base point on s2 ( radians ) and the position along the fibre.
+pub const ATTRIBUTE_HOPF_FIBRE: MeshVertexAttribute =
+    MeshVertexAttribute::new("Hopf_Fibre", 0x686f_7066, VertexFormat::Float32x3);
+
+/// A point on a Hopf surface, in fibre coordinates.
+#[derive(Clone, Copy, Debug)]
+pub struct FibrePoint {
+    /// The base point of the fibre.
+    pub sp: SurfacePoint,
+    /// The position along the fibre.
+    pub alpha: f32,
+}
+
+/// Maps a point on a Hopf mesh back onto its fibre.
+///
+/// `point` is in the mesh's local space, typically a picking hit passed through
+/// the inverse of the entity's `GlobalTransform`.
+///
+/// The fibre coordinates are interpolated across the triangle nearest the point,
+/// so the point is expected to lie on the surface.
+/// Every triangle is tested, when the hit triangle is known prefer
+/// [`fibre_point_on_triangle`].
+///
+/// Returns `None` when the mesh does not carry [`ATTRIBUTE_HOPF_FIBRE`].
+#[must_use]
+pub fn fibre_point_at(mesh: &Mesh, point: Vec3) -> Option<FibrePoint> {
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        return None;
+    };
+    let n_triangles = mesh.indices().map_or(positions.len(), Indices::len) / 3;
+
+    // ( distance to the plane, triangle index, barycentric weights )
+    let mut best: Option<(f32, usize, Vec3)> = None;
+    for t in 0..n_triangles {
+        let Some(triangle) = triangle(mesh, t) else {
+            continue;
+        };
+        let [a, b, c] = triangle.map(|i| Vec3::from(positions[i]));
+        let Some(weights) = barycentric(a, b, c, point) else {
+            continue;
+        };
+        // Allow for hits landing on a shared edge.
+        if weights.min_element() < -1e-3 {
+            continue;
+        }
+        let distance = (point - a).dot((b - a).cross(c - a).normalize()).abs();
+        if best.is_none_or(|(d, _, _)| distance < d) {
+            best = Some((distance, t, weights));
+        }
+    }
+
+    let (_, t, weights) = best?;
+    fibre_point_on_triangle(mesh, t, weights)
+}
+
+/// The fibre coordinates at a point within triangle `triangle_index` of a Hopf mesh.
+///
+/// `barycentric` weighs the triangle's vertices in index order, as reported
+/// by a mesh ray cast, see `RayMeshHit`.
+///
+/// Returns `None` when the mesh does not carry [`ATTRIBUTE_HOPF_FIBRE`],
+/// or has no such triangle.
+#[must_use]
+pub fn fibre_point_on_triangle(
+    mesh: &Mesh,
+    triangle_index: usize,
+    barycentric: Vec3,
+) -> Option<FibrePoint> {
+    let Some(VertexAttributeValues::Float32x3(fibres)) = mesh.attribute(ATTRIBUTE_HOPF_FIBRE)
+    else {
+        return None;
+    };
+    let [a, b, c] = triangle(mesh, triangle_index)?;
+    let (fa, fb, fc) = (fibres.get(a)?, fibres.get(b)?, fibres.get(c)?);
+    let [lat, lon, alpha] = [0, 1, 2].map(|k| {
+        barycentric
+            .x
+            .mul_add(fa[k], barycentric.y.mul_add(fb[k], barycentric.z * fc[k]))
+    });
+
+    Some(FibrePoint {
+        sp: SurfacePoint { lat, lon },
+        alpha,
+    })
+}
+
+// The vertex indices of triangle `t`, in an indexed or a plain triangle list.
+fn triangle(mesh: &Mesh, t: usize) -> Option<[usize; 3]> {
+    let vertex = |k: usize| match mesh.indices() {
+        Some(Indices::U16(indices)) => indices.get(k).map(|i| usize::from(*i)),
+        Some(Indices::U32(indices)) => indices.get(k).and_then(|i| usize::try_from(*i).ok()),
+        None => Some(k),
+    };
+    Some([vertex(3 * t)?, vertex(3 * t + 1)?, vertex(3 * t + 2)?])
+}
+
+// Barycentric weights of the projection of `p` onto the plane of a triangle.
+//
+// None for a degenerate triangle.
+fn barycentric(a: Vec3, b: Vec3, c: Vec3, p: Vec3) -> Option<Vec3> {
+    let (v0, v1, v2) = (b - a, c - a, p - a);
+    let d00 = v0.dot(v0);
+    let d01 = v0.dot(v1);
+    let d11 = v1.dot(v1);
+    let d20 = v2.dot(v0);
+    let d21 = v2.dot(v1);
+
+    let denom = d00.mul_add(d11, -d01 * d01);
+    if denom <= f32::EPSILON {
+        return None;
+    }
+    let v = d11.mul_add(d20, -d01 * d21) / denom;
+    let w = d00.mul_add(d21, -d01 * d20) / denom;
+    Some(Vec3::new(1_f32 - v - w, v, w))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hopf::HopfSurface;
+    use bevy_mesh::MeshBuilder;
+    use bevy_mesh::Meshable;
+
+    #[test]
+    fn vertex_maps_to_its_fibre() {
+        let surface = HopfSurface {
+            n_loops: 8,
+            n_points_per_loop: 20,
+            ..HopfSurface::default()
+        };
+        let builder = surface
+            .mesh()
+            .try_construct()
+            .expect("Failed to construct mesh");
+        let mesh = builder.build();
+
+        // A vertex in the middle of the grid.
+        let k = 4 * 20 + 7;
+        let found = fibre_point_at(&mesh, builder.vertex_buffer[k]).expect("fibre attribute");
+        let [lat, lon, alpha] = builder.fibre_store[k];
+        assert!((found.sp.lat - lat).abs() < 1e-4);
+        assert!((found.sp.lon - lon).abs() < 1e-4);
+        assert!((found.alpha - alpha).abs() < 1e-3);
+    }
+
+    #[test]
+    fn hit_triangle_matches_the_search() {
+        let surface = HopfSurface {
+            n_loops: 8,
+            n_points_per_loop: 20,
+            ..HopfSurface::default()
+        };
+        let mesh = surface.mesh().build();
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            panic!("positions");
+        };
+
+        let t = 100;
+        let [a, b, c] = triangle(&mesh, t)
+            .unwrap()
+            .map(|i| Vec3::from(positions[i]));
+        let weights = Vec3::new(0.2, 0.3, 0.5);
+        let point = weights.x * a + weights.y * b + weights.z * c;
+
+        let hit = fibre_point_on_triangle(&mesh, t, weights).expect("fibre attribute");
+        let found = fibre_point_at(&mesh, point).expect("fibre attribute");
+        assert!((hit.sp.lat - found.sp.lat).abs() < 1e-4);
+        assert!((hit.sp.lon - found.sp.lon).abs() < 1e-4);
+        assert!((hit.alpha - found.alpha).abs() < 1e-3);
+
+        let n_triangles = mesh.indices().map_or(positions.len(), Indices::len) / 3;
+        assert!(fibre_point_on_triangle(&mesh, n_triangles, weights).is_none());
+    }
+
+    #[test]
+    fn requires_fibre_attribute() {
+        let mesh = Mesh::from(bevy::math::primitives::Cuboid::default());
+        assert!(fibre_point_at(&mesh, Vec3::ZERO).is_none());
+    }
+}
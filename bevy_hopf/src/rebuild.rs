@@ -0,0 +1,457 @@
+use bevy::asset::Assets;
+use bevy::camera::primitives::Aabb;
+use bevy::ecs::component::Component;
+use bevy::ecs::entity::Entity;
+use bevy::ecs::hierarchy::ChildOf;
+use bevy::ecs::query::Changed;
+use bevy::ecs::query::Or;
+use bevy::ecs::resource::Resource;
+use bevy::ecs::system::Commands;
+use bevy::ecs::system::Query;
+use bevy::ecs::system::Res;
+use bevy::ecs::system::ResMut;
+use bevy::log::warn;
+use bevy::prelude::Mesh3d;
+use bevy::reflect::Reflect;
+use bevy::tasks::AsyncComputeTaskPool;
+use bevy::tasks::Task;
+use bevy::tasks::futures::check_ready;
+use bevy_mesh::Mesh;
+use bevy_mesh::MeshBuilder;
+use bevy_mesh::Meshable;
+
+use crate::fibre::HopfFibre;
+use crate::hopf::HopfSurface;
+use crate::lod::HopfLod;
+use crate::lod::HopfLodLevel;
+use crate::lod::build_lod_meshes;
+use crate::lod::swap_lod_levels;
+
+/// Controls how meshes are regenerated when a [`HopfSurface`] changes.
+#[derive(Clone, Debug, Reflect, Resource)]
+pub struct HopfMeshSettings {
+    /// Build meshes on the [`AsyncComputeTaskPool`], rather than stalling the frame.
+    pub asynchronous: bool,
+    /// While an asynchronous build is in flight a low resolution preview is shown.
+    ///
+    /// The loop and point counts are divided by this factor, 1 disables the preview.
+    pub preview_divisor: u16,
+}
+
+impl Default for HopfMeshSettings {
+    fn default() -> Self {
+        Self {
+            asynchronous: true,
+            preview_divisor: 4,
+        }
+    }
+}
+
+/// Meshes being built on the [`AsyncComputeTaskPool`].
+///
+/// Replacing or removing this component drops the task, which cancels the build.
+/// The task yields the full resolution mesh, followed by any [`HopfLod`] levels,
+/// or `None` when the surface cannot be built.
+#[derive(Component, Debug)]
+pub struct HopfMeshTask(pub Task<Option<Vec<Mesh>>>);
+
+impl HopfMeshTask {
+    fn spawn(surface: &HopfSurface, n_coarse: usize) -> Self {
+        let surface = surface.clone();
+        Self(AsyncComputeTaskPool::get().spawn(async move { try_build(&surface, n_coarse) }))
+    }
+}
+
+/// A low resolution preview being built on the [`AsyncComputeTaskPool`],
+/// see [`HopfMeshSettings::preview_divisor`].
+///
+/// Built alongside the full resolution [`HopfMeshTask`], and dropped unseen
+/// should the full resolution mesh arrive first.
+/// Replacing or removing this component drops the task, which cancels the build.
+#[derive(Component, Debug)]
+pub struct HopfPreviewTask(pub Task<Option<Mesh>>);
+
+// Unlike `Mesh::from`, a surface which cannot be built yields no mesh.
+fn try_build(surface: &HopfSurface, n_coarse: usize) -> Option<Vec<Mesh>> {
+    match build_lod_meshes(surface, n_coarse) {
+        Ok(meshes) => Some(meshes),
+        Err(e) => {
+            warn!("{e}");
+            None
+        }
+    }
+}
+
+// Swap in the full mesh on the entity, and the coarser levels on its children.
+fn swap_meshes(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    entity: Entity,
+    mesh3d: Option<&Mesh3d>,
+    levels: &Query<(Entity, &HopfLodLevel, &ChildOf, Option<&Mesh3d>)>,
+    built: Vec<Mesh>,
+) {
+    let mut built = built.into_iter();
+    if let Some(full) = built.next() {
+        swap_mesh(commands, meshes, entity, mesh3d, full);
+    }
+    swap_lod_levels(commands, meshes, entity, levels, built.collect());
+}
+
+// Replace the entity's mesh asset in place, so the handle stays valid.
+//
+// The stale bounds are removed, to be recalculated for the new mesh.
+pub(crate) fn swap_mesh(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    entity: Entity,
+    mesh3d: Option<&Mesh3d>,
+    mesh: Mesh,
+) {
+    match mesh3d.and_then(|Mesh3d(handle)| meshes.get_mut(handle)) {
+        Some(existing) => {
+            *existing = mesh;
+            commands.entity(entity).remove::<Aabb>();
+        }
+        None => {
+            commands.entity(entity).insert(Mesh3d(meshes.add(mesh)));
+        }
+    }
+}
+
+/// Rebuilds the [`Mesh3d`] of every entity whose [`HopfSurface`] has changed.
+///
+/// Change detection also fires when the component is first added,
+/// so a [`Mesh3d`] is inserted for freshly spawned surfaces.
+///
+/// In asynchronous mode nothing is built on the main thread. A
+/// [`HopfPreviewTask`] builds the preview while a [`HopfMeshTask`] builds the
+/// full resolution mesh, any outdated build still in flight is cancelled.
+///
+/// A surface which cannot be built, keeps its previous mesh.
+///
+/// Surfaces with a [`HopfLod`] also rebuild their levels, when either changes.
+/// The preview only replaces the full resolution mesh.
+#[allow(clippy::needless_pass_by_value, clippy::type_complexity)]
+pub fn rebuild_hopf_meshes(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    settings: Res<HopfMeshSettings>,
+    surfaces: Query<
+        (Entity, &HopfSurface, Option<&HopfLod>, Option<&Mesh3d>),
+        Or<(Changed<HopfSurface>, Changed<HopfLod>)>,
+    >,
+    levels: Query<(Entity, &HopfLodLevel, &ChildOf, Option<&Mesh3d>)>,
+) {
+    for (entity, surface, lod, mesh3d) in &surfaces {
+        let n_coarse = lod.map_or(0, HopfLod::n_coarse);
+        if !settings.asynchronous {
+            if let Some(built) = try_build(surface, n_coarse) {
+                swap_meshes(&mut commands, &mut meshes, entity, mesh3d, &levels, built);
+            }
+            continue;
+        }
+
+        // Dropping the previous tasks cancels them.
+        let mut entity = commands.entity(entity);
+        entity.insert(HopfMeshTask::spawn(surface, n_coarse));
+        if settings.preview_divisor > 1 {
+            let preview = surface.preview(settings.preview_divisor);
+            let task = AsyncComputeTaskPool::get().spawn(async move {
+                try_build(&preview, 0).and_then(|built| built.into_iter().next())
+            });
+            entity.insert(HopfPreviewTask(task));
+        } else {
+            entity.remove::<HopfPreviewTask>();
+        }
+    }
+}
+
+/// Swaps in meshes whose asynchronous build has completed.
+///
+/// A completed full resolution build also drops any preview still in flight.
+#[allow(clippy::type_complexity)]
+pub fn poll_hopf_mesh_tasks(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut previews: Query<(Entity, &mut HopfPreviewTask, Option<&Mesh3d>)>,
+    mut tasks: Query<(Entity, &mut HopfMeshTask, Option<&Mesh3d>)>,
+    levels: Query<(Entity, &HopfLodLevel, &ChildOf, Option<&Mesh3d>)>,
+) {
+    // Previews first, so a full mesh completing in the same frame replaces it.
+    for (entity, mut task, mesh3d) in &mut previews {
+        if let Some(preview) = check_ready(&mut task.0) {
+            if let Some(preview) = preview {
+                swap_mesh(&mut commands, &mut meshes, entity, mesh3d, preview);
+            }
+            commands.entity(entity).remove::<HopfPreviewTask>();
+        }
+    }
+
+    for (entity, mut task, mesh3d) in &mut tasks {
+        if let Some(built) = check_ready(&mut task.0) {
+            if let Some(built) = built {
+                swap_meshes(&mut commands, &mut meshes, entity, mesh3d, &levels, built);
+            }
+            commands
+                .entity(entity)
+                .remove::<(HopfMeshTask, HopfPreviewTask)>();
+        }
+    }
+}
+
+/// Rebuilds the [`Mesh3d`] of every entity whose [`HopfFibre`] has changed.
+///
+/// A single fibre is cheap, so it is always built synchronously.
+pub fn rebuild_hopf_fibres(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    fibres: Query<(Entity, &HopfFibre, Option<&Mesh3d>), Changed<HopfFibre>>,
+) {
+    for (entity, fibre, mesh3d) in &fibres {
+        let mesh = fibre.mesh().build();
+        swap_mesh(&mut commands, &mut meshes, entity, mesh3d, mesh);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::app::App;
+    use bevy::asset::AssetApp;
+    use bevy::asset::AssetPlugin;
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::prelude::MinimalPlugins;
+
+    use super::*;
+    use crate::HopfPlugin;
+
+    fn n_vertices(app: &App, entity: Entity) -> usize {
+        let world = app.world();
+        let Mesh3d(handle) = world.get::<Mesh3d>(entity).expect("Mesh3d inserted");
+        world
+            .resource::<Assets<Mesh>>()
+            .get(handle)
+            .expect("mesh asset")
+            .count_vertices()
+    }
+
+    fn app(settings: HopfMeshSettings) -> App {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            HopfPlugin::default(),
+        ))
+        .init_asset::<Mesh>()
+        .insert_resource(settings);
+        app
+    }
+
+    // Update until no entity holds the task component `T`.
+    fn settle_tasks<T: Component>(app: &mut App) {
+        for _ in 0..1000 {
+            app.update();
+            let mut tasks = app.world_mut().query::<&T>();
+            if tasks.iter(app.world()).next().is_none() {
+                return;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        panic!("task did not complete");
+    }
+
+    // Update until every asynchronous build has been swapped in.
+    fn settle(app: &mut App) {
+        settle_tasks::<HopfPreviewTask>(app);
+        settle_tasks::<HopfMeshTask>(app);
+    }
+
+    #[test]
+    fn mesh_follows_surface() {
+        let mut app = app(HopfMeshSettings {
+            asynchronous: false,
+            ..HopfMeshSettings::default()
+        });
+
+        let entity = app
+            .world_mut()
+            .spawn(HopfSurface {
+                n_loops: 5,
+                n_points_per_loop: 10,
+                ..HopfSurface::default()
+            })
+            .id();
+        app.update();
+        // Flat shading, 3 vertices per triangle.
+        assert_eq!(n_vertices(&app, entity), 4 * 9 * 2 * 3);
+        let handle = app.world().get::<Mesh3d>(entity).cloned();
+
+        app.world_mut()
+            .get_mut::<HopfSurface>(entity)
+            .expect("surface")
+            .n_loops = 6;
+        app.update();
+        assert_eq!(n_vertices(&app, entity), 5 * 9 * 2 * 3);
+        // Replaced in place.
+        assert_eq!(app.world().get::<Mesh3d>(entity).cloned(), handle);
+    }
+
+    #[test]
+    fn invalid_surface_keeps_mesh() {
+        let mut app = app(HopfMeshSettings {
+            asynchronous: false,
+            ..HopfMeshSettings::default()
+        });
+
+        let entity = app
+            .world_mut()
+            .spawn(HopfSurface {
+                n_loops: 5,
+                n_points_per_loop: 10,
+                ..HopfSurface::default()
+            })
+            .id();
+        app.update();
+
+        app.world_mut()
+            .get_mut::<HopfSurface>(entity)
+            .expect("surface")
+            .n_loops = 0;
+        app.update();
+        assert_eq!(n_vertices(&app, entity), 4 * 9 * 2 * 3);
+    }
+
+    #[test]
+    fn fibre_mesh_follows_component() {
+        let mut app = app(HopfMeshSettings::default());
+
+        let entity = app
+            .world_mut()
+            .spawn(HopfFibre {
+                n_points: 30,
+                ..HopfFibre::default()
+            })
+            .id();
+        app.update();
+        assert_eq!(n_vertices(&app, entity), 30);
+
+        app.world_mut()
+            .get_mut::<HopfFibre>(entity)
+            .expect("fibre")
+            .n_points = 40;
+        app.update();
+        assert_eq!(n_vertices(&app, entity), 40);
+    }
+
+    #[test]
+    fn lod_levels_are_children() {
+        let mut app = app(HopfMeshSettings::default());
+
+        let entity = app
+            .world_mut()
+            .spawn((
+                HopfSurface {
+                    n_loops: 9,
+                    n_points_per_loop: 17,
+                    ..HopfSurface::default()
+                },
+                HopfLod {
+                    distances: vec![10.0, 20.0],
+                    fade: 0.0,
+                },
+            ))
+            .id();
+        settle(&mut app);
+        app.update();
+
+        let mut levels = app.world_mut().query::<(Entity, &HopfLodLevel, &ChildOf)>();
+        let mut children = levels
+            .iter(app.world())
+            .filter(|(_, _, child_of)| child_of.parent() == entity)
+            .map(|(child, HopfLodLevel(level), _)| (*level, child))
+            .collect::<Vec<_>>();
+        children.sort_unstable();
+        assert_eq!(children.len(), 2);
+        assert_eq!(n_vertices(&app, entity), 8 * 16 * 6);
+        assert_eq!(n_vertices(&app, children[0].1), 4 * 8 * 6);
+        assert_eq!(n_vertices(&app, children[1].1), 2 * 4 * 6);
+
+        // Dropping the levels despawns them.
+        app.world_mut().entity_mut(entity).remove::<HopfLod>();
+        app.update();
+        assert_eq!(
+            levels
+                .iter(app.world())
+                .filter(|(_, _, child_of)| child_of.parent() == entity)
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn preview_then_full_mesh() {
+        let mut app = app(HopfMeshSettings::default());
+
+        let entity = app
+            .world_mut()
+            .spawn(HopfSurface {
+                n_loops: 20,
+                n_points_per_loop: 40,
+                ..HopfSurface::default()
+            })
+            .id();
+        // The 5 x 10 preview and the full mesh are built side by side.
+        app.world_mut()
+            .run_system_once(rebuild_hopf_meshes)
+            .expect("rebuild");
+        assert!(app.world().get::<HopfPreviewTask>(entity).is_some());
+        assert!(app.world().get::<HopfMeshTask>(entity).is_some());
+
+        settle(&mut app);
+        assert_eq!(n_vertices(&app, entity), 19 * 39 * 2 * 3);
+    }
+
+    #[test]
+    fn full_mesh_drops_the_preview() {
+        let mut app = app(HopfMeshSettings::default());
+
+        let surface = HopfSurface {
+            n_loops: 5,
+            n_points_per_loop: 10,
+            ..HopfSurface::default()
+        };
+        // A preview which never completes, behind the full build.
+        let stalled = AsyncComputeTaskPool::get().spawn(std::future::pending());
+        let entity = app
+            .world_mut()
+            .spawn((HopfMeshTask::spawn(&surface, 0), HopfPreviewTask(stalled)))
+            .id();
+
+        settle_tasks::<HopfMeshTask>(&mut app);
+        assert!(app.world().get::<HopfPreviewTask>(entity).is_none());
+        assert_eq!(n_vertices(&app, entity), 4 * 9 * 2 * 3);
+    }
+
+    #[test]
+    fn outdated_build_is_cancelled() {
+        let mut app = app(HopfMeshSettings::default());
+
+        let entity = app
+            .world_mut()
+            .spawn(HopfSurface {
+                n_loops: 200,
+                n_points_per_loop: 400,
+                ..HopfSurface::default()
+            })
+            .id();
+        app.update();
+
+        // Change the parameters while the first build is in flight.
+        app.world_mut()
+            .get_mut::<HopfSurface>(entity)
+            .expect("surface")
+            .n_loops = 8;
+        settle(&mut app);
+        assert_eq!(n_vertices(&app, entity), 7 * 399 * 2 * 3);
+    }
+}
@@ -0,0 +1,68 @@
+//! Compares two generated PLY or OBJ models, within a tolerance.
+//!
+//! hopf-diff <a> <b> [--tolerance X]
+//!
+//! Exits with 0 when the models match, 1 when they differ
+//! and 2 when either file cannot be read.
+#![deny(clippy::all)]
+#![warn(clippy::cargo)]
+#![warn(clippy::complexity)]
+#![warn(clippy::pedantic)]
+#![warn(clippy::nursery)]
+#![warn(clippy::perf)]
+#![warn(missing_debug_implementations)]
+#![warn(missing_docs)]
+
+use hopf::diff::diff_files;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: hopf-diff <a> <b> [--tolerance X]";
+
+// Matches the precision of the ASCII PLY and OBJ writers.
+const DEFAULT_TOLERANCE: f32 = 1e-5;
+
+fn parse_args() -> Result<(PathBuf, PathBuf, f32), String> {
+    let mut paths = vec![];
+    let mut tolerance = DEFAULT_TOLERANCE;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--tolerance" {
+            let value = args.next().ok_or("--tolerance expects a value")?;
+            tolerance = value
+                .parse()
+                .map_err(|_| format!("Invalid tolerance {value}"))?;
+        } else {
+            paths.push(PathBuf::from(arg));
+        }
+    }
+    match <[PathBuf; 2]>::try_from(paths) {
+        Ok([a, b]) => Ok((a, b, tolerance)),
+        Err(_) => Err(String::from("Expected two files")),
+    }
+}
+
+fn main() -> ExitCode {
+    let (a, b, tolerance) = match parse_args() {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{message}\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    match diff_files(&a, &b, tolerance) {
+        Ok(report) => {
+            println!("{report}");
+            if report.is_match() {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::from(2)
+        }
+    }
+}
@@ -0,0 +1,337 @@
+use crate::Vertex;
+use crate::obj::ObjContents;
+use crate::ply::Ply;
+use std::fmt::Display;
+use std::fs::File;
+use std::io::BufReader;
+use std::io::Error;
+use std::path::Path;
+
+use glam::Vec3;
+
+/// Geometry loaded from a PLY or OBJ file, reduced to what is compared.
+///
+/// Indices are zero based, whatever the source format.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Model {
+    /// Vertices in file order.
+    pub vertices: Vec<Vertex>,
+    /// Faces of any size.
+    pub faces: Vec<Vec<usize>>,
+    /// Edges, OBJ polylines are split into consecutive pairs.
+    pub edges: Vec<[usize; 2]>,
+}
+
+impl From<Ply> for Model {
+    fn from(ply: Ply) -> Self {
+        let faces = ply
+            .faces()
+            .map(|face| face.iter().map(|i| *i as usize).collect())
+            .collect();
+        Self {
+            faces,
+            edges: ply
+                .edge_store
+                .iter()
+                .map(|edge| edge.map(|i| i as usize))
+                .collect(),
+            vertices: ply.vertex_buffer,
+        }
+    }
+}
+
+impl From<ObjContents> for Model {
+    fn from(obj: ObjContents) -> Self {
+        let faces = obj
+            .objects
+            .iter()
+            .flat_map(|object| &object.faces)
+            .map(|face| face.iter().map(|corner| corner.vertex).collect())
+            .collect();
+        let edges = obj
+            .objects
+            .iter()
+            .flat_map(|object| &object.lines)
+            .flat_map(|line| line.windows(2).map(|pair| [pair[0], pair[1]]))
+            .collect();
+        Self {
+            vertices: obj.vertices,
+            faces,
+            edges,
+        }
+    }
+}
+
+impl Model {
+    /// Loads a `.ply` or `.obj` file, chosen by extension.
+    ///
+    /// # Errors
+    ///   When the extension is unknown, or the file cannot be read.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        let input = BufReader::new(File::open(path)?);
+        match extension.as_deref() {
+            Some("ply") => Ply::read(input).map(Self::from),
+            Some("obj") => ObjContents::read(input).map(Self::from),
+            _ => Err(Error::other(format!(
+                "Cannot load {}, expected a .ply or .obj file.",
+                path.display()
+            ))),
+        }
+    }
+
+    // The smallest and largest index used by any face or edge.
+    fn index_range(&self) -> Option<(usize, usize)> {
+        let indices = self
+            .faces
+            .iter()
+            .flatten()
+            .chain(self.edges.iter().flatten());
+        indices.fold(None, |range, i| match range {
+            None => Some((*i, *i)),
+            Some((lo, hi)) => Some((lo.min(*i), hi.max(*i))),
+        })
+    }
+}
+
+/// The differences between two models, see [`diff`].
+///
+/// Vertices are matched by index, generated models are deterministic.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DiffReport {
+    /// Distances above this are counted as differences.
+    pub tolerance: f32,
+    /// Vertex counts of each model.
+    pub n_vertices: [usize; 2],
+    /// Face counts of each model.
+    pub n_faces: [usize; 2],
+    /// Edge counts of each model.
+    pub n_edges: [usize; 2],
+    /// The smallest and largest index referenced by each model.
+    pub index_ranges: [Option<(usize, usize)>; 2],
+    /// Largest distance between matching vertices.
+    pub max_distance: f32,
+    /// Mean distance between matching vertices.
+    pub mean_distance: f32,
+    /// Root mean square distance between matching vertices.
+    pub rms_distance: f32,
+    /// The vertex furthest from its match.
+    pub worst_vertex: Option<usize>,
+    /// Number of matching vertices further apart than `tolerance`.
+    pub n_vertices_beyond: usize,
+    /// Number of matching faces, with different indices.
+    pub n_faces_differing: usize,
+    /// Number of matching edges, with different indices.
+    pub n_edges_differing: usize,
+}
+
+impl DiffReport {
+    /// The topology differs, counts, index ranges or indices.
+    #[must_use]
+    pub fn topology_differs(&self) -> bool {
+        self.n_vertices[0] != self.n_vertices[1]
+            || self.n_faces[0] != self.n_faces[1]
+            || self.n_edges[0] != self.n_edges[1]
+            || self.index_ranges[0] != self.index_ranges[1]
+            || self.n_faces_differing > 0
+            || self.n_edges_differing > 0
+    }
+
+    /// The models match, within the tolerance.
+    #[must_use]
+    pub fn is_match(&self) -> bool {
+        !self.topology_differs() && self.n_vertices_beyond == 0
+    }
+}
+
+impl Display for DiffReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let [a, b] = self.n_vertices;
+        writeln!(f, "vertices: {a} vs {b}")?;
+        let [a, b] = self.n_faces;
+        writeln!(f, "faces: {a} vs {b} ( {} differ )", self.n_faces_differing)?;
+        let [a, b] = self.n_edges;
+        writeln!(f, "edges: {a} vs {b} ( {} differ )", self.n_edges_differing)?;
+        let [a, b] = self.index_ranges;
+        writeln!(f, "index ranges: {a:?} vs {b:?}")?;
+        writeln!(
+            f,
+            "distance: max {} mean {} rms {}",
+            self.max_distance, self.mean_distance, self.rms_distance
+        )?;
+        if let Some(worst) = self.worst_vertex {
+            writeln!(f, "worst vertex: {worst}")?;
+        }
+        writeln!(
+            f,
+            "{} vertices beyond tolerance {}",
+            self.n_vertices_beyond, self.tolerance
+        )?;
+        write!(f, "{}", if self.is_match() { "match" } else { "differ" })
+    }
+}
+
+/// Compares two models, vertex by vertex and face by face.
+///
+/// Distances are only measured over the vertices both models share.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn diff(a: &Model, b: &Model, tolerance: f32) -> DiffReport {
+    let mut report = DiffReport {
+        tolerance,
+        n_vertices: [a.vertices.len(), b.vertices.len()],
+        n_faces: [a.faces.len(), b.faces.len()],
+        n_edges: [a.edges.len(), b.edges.len()],
+        index_ranges: [a.index_range(), b.index_range()],
+        ..DiffReport::default()
+    };
+
+    let mut sum = 0_f64;
+    let mut sum_squares = 0_f64;
+    let mut n = 0_usize;
+    for (i, (va, vb)) in a.vertices.iter().zip(&b.vertices).enumerate() {
+        let d = Vec3::from(*va).distance(Vec3::from(*vb));
+        // NaN counts as beyond any tolerance.
+        if d.is_nan() || d > tolerance {
+            report.n_vertices_beyond += 1;
+        }
+        if report.worst_vertex.is_none() || d > report.max_distance || d.is_nan() {
+            report.max_distance = d;
+            report.worst_vertex = Some(i);
+        }
+        sum += f64::from(d);
+        sum_squares += f64::from(d) * f64::from(d);
+        n += 1;
+    }
+    if n > 0 {
+        #[allow(clippy::cast_possible_truncation)]
+        {
+            report.mean_distance = (sum / n as f64) as f32;
+            report.rms_distance = (sum_squares / n as f64).sqrt() as f32;
+        }
+    }
+
+    report.n_faces_differing = a
+        .faces
+        .iter()
+        .zip(&b.faces)
+        .filter(|(fa, fb)| fa != fb)
+        .count();
+    report.n_edges_differing = a
+        .edges
+        .iter()
+        .zip(&b.edges)
+        .filter(|(ea, eb)| ea != eb)
+        .count();
+    report
+}
+
+/// Loads and compares two `.ply` or `.obj` files, see [`diff`].
+///
+/// # Errors
+///   When either file cannot be loaded.
+pub fn diff_files(a: &Path, b: &Path, tolerance: f32) -> Result<DiffReport, Error> {
+    Ok(diff(&Model::load(a)?, &Model::load(b)?, tolerance))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square() -> Model {
+        Model {
+            vertices: [Vec3::ZERO, Vec3::X, Vec3::ONE, Vec3::Y]
+                .map(Vertex)
+                .to_vec(),
+            faces: vec![vec![0, 1, 2, 3]],
+            edges: vec![[0, 2]],
+        }
+    }
+
+    #[test]
+    fn identical_models_match() {
+        let report = diff(&square(), &square(), 0.0);
+        assert!(report.is_match());
+        assert_eq!(report.index_ranges[0], Some((0, 3)));
+        assert!(report.max_distance.abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn distances_within_tolerance() {
+        let mut moved = square();
+        moved.vertices[2] = Vertex(Vec3::ONE + Vec3::Z * 1e-3);
+
+        let report = diff(&square(), &moved, 1e-2);
+        assert!(report.is_match());
+        assert_eq!(report.worst_vertex, Some(2));
+        assert!((report.mean_distance - 0.25e-3).abs() < 1e-6);
+
+        let report = diff(&square(), &moved, 1e-4);
+        assert!(!report.is_match());
+        assert!(!report.topology_differs());
+        assert_eq!(report.n_vertices_beyond, 1);
+    }
+
+    #[test]
+    fn topology_changes_are_flagged() {
+        let mut flipped = square();
+        flipped.faces[0].reverse();
+        let report = diff(&square(), &flipped, 1.0);
+        assert_eq!(report.n_faces_differing, 1);
+        assert!(report.topology_differs());
+
+        let mut extra = square();
+        extra.vertices.push(Vertex(Vec3::Z));
+        extra.edges.push([3, 4]);
+        let report = diff(&square(), &extra, 1.0);
+        assert_eq!(report.n_vertices, [4, 5]);
+        assert_eq!(report.index_ranges[1], Some((0, 4)));
+        assert!(!report.is_match());
+    }
+
+    #[test]
+    fn fibre_matches_golden_model() {
+        use crate::fibre::Fibre;
+        use crate::generate_ply;
+        use crate::sp::SurfacePoint;
+        use std::io::BufWriter;
+
+        // The fibre written by point2ply, regenerate with point2ply/go.sh.
+        let alpha = 0_f32..=4.0 * core::f32::consts::PI;
+        let fibre = Fibre::new(
+            SurfacePoint {
+                lat: 5.0_f32.to_radians(),
+                lon: 5.0_f32.to_radians(),
+            },
+            &alpha,
+        );
+        let (points, _) = fibre.build_uniform::<20>();
+        let mut out = BufWriter::new(vec![]);
+        generate_ply(points.into_iter(), &mut out).unwrap();
+        let buffer = out.into_inner().unwrap();
+
+        let golden = include_str!("../../point2ply/a.ply");
+        let report = diff(
+            &Model::from(Ply::read(golden.as_bytes()).unwrap()),
+            &Model::from(Ply::read(buffer.as_slice()).unwrap()),
+            1e-5,
+        );
+        assert!(report.is_match(), "{report}");
+    }
+
+    #[test]
+    fn formats_agree() {
+        // The same polyline, read back from each format.
+        let ply = "ply\nformat ascii 1.0\nelement vertex 3\nproperty float x\n\
+                   property float y\nproperty float z\nelement edge 2\n\
+                   property int vertex1\nproperty int vertex2\nend_header\n\
+                   0 0 0\n1 0 0\n1 1 0\n0 1\n1 2\n";
+        let obj = "o fibre_0\nv 0 0 0\nv 1 0 0\nv 1 1 0\nl 1 2 3\n";
+        let a = Model::from(Ply::read(ply.as_bytes()).unwrap());
+        let b = Model::from(ObjContents::read(obj.as_bytes()).unwrap());
+        assert_eq!(a, b);
+    }
+}
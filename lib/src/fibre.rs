@@ -5,14 +5,27 @@ use core::ops::RangeInclusive;
 use std::fmt::Display;
 use std::fmt::Formatter;
 
+use glam::Mat4;
+use glam::Vec4;
+
 use crate::Vertex;
+use crate::length::path_length_lut;
 use crate::length::resample_fibre;
+use crate::length::resample_lut;
 use crate::project;
 use crate::sp::SurfacePoint;
 
 // The domain of a fibre is 0..4PI
 static ALPHA_MAX: f32 = 4_f32 * core::f32::consts::PI;
 
+// Initial LUT resolution, as a multiple of the number of output points.
+static LUT_OVERSAMPLE: usize = 16;
+// Memory bound on the LUT, independent of the number of tries allowed.
+static LUT_MAX: usize = 1 << 22;
+// Each doubling of the LUT must cut the spacing spread below this fraction of
+// the best so far, otherwise f32 precision has been reached.
+static SPREAD_PROGRESS: f32 = 0.9;
+
 // The Range is not 'inclusive'  as it blocks the north pole.
 // Under sterographic the projection of a point at the north pole is undefined.
 static LAT_RANGE: Range<f32> = -core::f32::consts::FRAC_PI_2..core::f32::consts::FRAC_PI_2;
@@ -31,6 +44,9 @@ pub struct Fibre<'a> {
     alpha: &'a RangeInclusive<f32>,
 
     sp: SurfacePoint,
+
+    // Rotation of S3, applied before the stereographic projection.
+    rotation: Mat4,
 }
 
 /// Setting extracted from polar coords.
@@ -50,13 +66,23 @@ pub enum FibreBuildError {
     NTriesExceed(u16),
     /// Too few tries allowed adjusting step size.
     NTriesTooLow(u16),
+    /// The fibre could not be evaluated, it passes through the projection pole.
+    NotFinite,
 }
 
 impl Error for FibreBuildError {}
 
 impl Display for FibreBuildError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "SuperError is here!")
+        match self {
+            Self::NTriesExceed(n_tries) => {
+                write!(f, "target accuracy not reached after {n_tries} tries")
+            }
+            Self::NTriesTooLow(n_tries) => {
+                write!(f, "{n_tries} tries is too few to build a fibre")
+            }
+            Self::NotFinite => write!(f, "the fibre passes through the projection pole"),
+        }
     }
 }
 
@@ -81,7 +107,20 @@ impl<'a> Fibre<'a> {
         debug_assert!(*alpha.end() >= 0_f32, "alpha_end {:#?}", alpha.end());
         debug_assert!(*alpha.end() <= ALPHA_MAX, "alpha_end {:#?}", alpha.end());
 
-        Self { alpha, sp }
+        Self {
+            alpha,
+            sp,
+            rotation: Mat4::IDENTITY,
+        }
+    }
+
+    /// Rotates S3 before the stereographic projection.
+    ///
+    /// `rotation` must be orthogonal, see [`crate::rotation`].
+    #[must_use]
+    pub const fn with_rotation(mut self, rotation: Mat4) -> Self {
+        self.rotation = rotation;
+        self
     }
 
     /// RAW Uniformly space in domain space results in highly un-evenly spaced output.
@@ -156,6 +195,78 @@ impl<'a> Fibre<'a> {
             .unzip()
     }
 
+    /// Returns `n_points` points uniformly spaced along the curve, and their alpha values.
+    ///
+    /// The (alpha, path length) LUT resolution is doubled on each try
+    /// until the spacing between points is uniform to within `tolerance`.
+    ///
+    /// The spacing is measured as the relative spread of the distance
+    /// between neighbouring points, (max - min) / mean.
+    ///
+    /// Near the projection pole f32 precision bounds the spread, so once a
+    /// doubling stops improving it ( or the LUT reaches its memory bound )
+    /// the most even try is returned, as [`Fibre::build_uniform`] would.
+    ///
+    /// # Errors
+    ///
+    /// `FibreBuildError::NTriesTooLow` if `n_tries` is zero.
+    ///
+    /// `FibreBuildError::NTriesExceed` if the spread is still improving,
+    /// but has not met `tolerance` after `n_tries`.
+    ///
+    /// `FibreBuildError::NotFinite` if the fibre passes through the projection pole.
+    pub fn try_build_uniform(
+        &self,
+        n_points: usize,
+        tolerance: f32,
+        n_tries: u16,
+    ) -> Result<(Vec<Vertex>, Vec<f32>), FibreBuildError> {
+        if n_tries == 0 {
+            return Err(FibreBuildError::NTriesTooLow(n_tries));
+        }
+
+        let fibre = self.projected_fibre();
+        let mut n_detailed = (n_points * LUT_OVERSAMPLE).max(2);
+        let mut best: Option<(f32, Vec<Vertex>, Vec<f32>)> = None;
+        for _ in 0..n_tries {
+            let lut = path_length_lut(&fibre, self.alpha, n_detailed);
+            // A sample landed on the projection pole.
+            if lut.last().is_none_or(|(_, d)| !d.is_finite()) {
+                return Err(FibreBuildError::NotFinite);
+            }
+            let alphas = resample_lut(&lut, n_points);
+            let points = alphas.iter().map(|a| fibre(*a)).collect::<Vec<_>>();
+
+            let spread = spacing_spread(&points);
+            if !spread.is_finite() {
+                return Err(FibreBuildError::NotFinite);
+            }
+            if spread <= tolerance {
+                return Ok((points, alphas));
+            }
+
+            let progress = best
+                .as_ref()
+                .is_none_or(|(best_spread, ..)| spread < SPREAD_PROGRESS * best_spread);
+            if best
+                .as_ref()
+                .is_none_or(|(best_spread, ..)| spread < *best_spread)
+            {
+                best = Some((spread, points, alphas));
+            }
+
+            n_detailed *= 2;
+            if !progress || n_detailed > LUT_MAX {
+                // No finer LUT will help, settle for the best so far.
+                return best
+                    .map(|(_, points, alphas)| (points, alphas))
+                    .ok_or(FibreBuildError::NotFinite);
+            }
+        }
+
+        Err(FibreBuildError::NTriesExceed(n_tries))
+    }
+
     // Solve for ξ1 and η.
     // Given a point on s2 (lat, long)
     //
@@ -173,7 +284,8 @@ impl<'a> Fibre<'a> {
 
         let η = z.acos() / 2.0;
         let sin2n = (2.0 * η).sin();
-        let x_div_sin2n = x / sin2n;
+        // Near the poles rounding can push the ratio just past ±1.
+        let x_div_sin2n = (x / sin2n).clamp(-1_f32, 1_f32);
         let ξ1 = (x_div_sin2n).acos();
 
         Settings { η, ξ1 }
@@ -194,17 +306,46 @@ impl<'a> Fibre<'a> {
         let Settings { η, ξ1 } = self.extract_settings();
 
         let (sin_η, cos_η) = η.sin_cos();
+        let rotation = self.rotation;
         // The domain of ξ2 is 0..4PI
         move |ξ2| {
             let X1 = f32::midpoint(ξ1, ξ2).cos() * sin_η;
             let X2 = f32::midpoint(ξ1, ξ2).sin() * sin_η;
             let X3 = ((ξ2 - ξ1) / 2_f32).cos() * cos_η;
             let X4 = ((ξ2 - ξ1) / 2_f32).sin() * cos_η;
-            project(X1, X2, X3, X4)
+            let [x, y, z, w] = (rotation * Vec4::new(X1, X2, X3, X4)).to_array();
+            project(x, y, z, w)
         }
     }
 }
 
+// Relative spread of the distance between neighbouring points.
+//
+// Zero when the points are evenly spaced.
+#[allow(clippy::cast_precision_loss)]
+fn spacing_spread(points: &[Vertex]) -> f32 {
+    if points.len() < 2 {
+        return 0_f32;
+    }
+    let (min, max, sum) = points.windows(2).fold(
+        (f32::INFINITY, f32::NEG_INFINITY, 0_f32),
+        |(min, max, sum), w| {
+            let d = (w[1] - w[0]).length();
+            (min.min(d), max.max(d), sum + d)
+        },
+    );
+    let mean = sum / (points.len() - 1) as f32;
+    if mean > 0_f32 {
+        (max - min) / mean
+    } else if mean == 0_f32 {
+        // All points coincide.
+        0_f32
+    } else {
+        // NaN, the fibre could not be evaluated.
+        f32::NAN
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -277,4 +418,145 @@ mod tests {
             "for a close path the first and last points must be close {first_point:#?} {last_point:#?} {delta}"
         );
     }
+
+    #[test]
+    fn uniform_within_tolerance() {
+        let alpha = 0_f32..=F32_4PI;
+        let fibre = Fibre::new(
+            SurfacePoint {
+                lat: 5.0_f32.to_radians(),
+                lon: 5.0_f32.to_radians(),
+            },
+            &alpha,
+        );
+
+        let (points, alphas) = fibre
+            .try_build_uniform(40, 1e-3, 10)
+            .expect("must converge");
+        assert_eq!(points.len(), 40);
+        assert_eq!(alphas.len(), 40);
+        assert!(spacing_spread(&points) <= 1e-3);
+
+        // A closed path.
+        let delta = (points[0] - points[39]).length();
+        assert!(delta < 1e-3, "first and last points must be close {delta}");
+    }
+
+    /// A fibre passing close to the projection pole is large and highly
+    /// non-uniform in alpha, this needs an accurate path length.
+    #[test]
+    fn uniform_large_fibre() {
+        let alpha = 0_f32..=F32_4PI;
+        let fibre = Fibre::new(
+            SurfacePoint {
+                lat: 45.0_f32.to_radians(),
+                lon: 180.0_f32.to_radians(),
+            },
+            &alpha,
+        );
+
+        let (points, _alphas) = fibre
+            .try_build_uniform(400, 1e-3, 10)
+            .expect("must converge");
+        assert!(spacing_spread(&points) <= 1e-3);
+    }
+
+    /// Rotations which leave X4 alone, are rotations of the projection.
+    #[test]
+    fn rotation_without_w_is_rigid() {
+        use crate::rotation::Plane;
+        use crate::rotation::plane_rotation;
+
+        let alpha = 0_f32..=F32_4PI;
+        let sp = SurfacePoint {
+            lat: 20.0_f32.to_radians(),
+            lon: 30.0_f32.to_radians(),
+        };
+        let angle = 0.7_f32;
+        let fibre = Fibre::new(sp, &alpha).projected_fibre();
+        let rotated = Fibre::new(sp, &alpha)
+            .with_rotation(plane_rotation(Plane::XY, angle))
+            .projected_fibre();
+
+        let q = glam::Quat::from_rotation_z(angle);
+        for a in [0_f32, 1_f32, 5_f32, 11_f32] {
+            let expected = q * glam::Vec3::from(fibre(a));
+            let observed = glam::Vec3::from(rotated(a));
+            assert!(
+                expected.abs_diff_eq(observed, 1e-5),
+                "{expected} {observed}"
+            );
+        }
+    }
+
+    #[test]
+    fn n_tries_contract() {
+        let alpha = 0_f32..=F32_4PI;
+        let fibre = Fibre::new(
+            SurfacePoint {
+                lat: 5.0_f32.to_radians(),
+                lon: 5.0_f32.to_radians(),
+            },
+            &alpha,
+        );
+
+        assert!(matches!(
+            fibre.try_build_uniform(40, 1e-3, 0),
+            Err(FibreBuildError::NTriesTooLow(0))
+        ));
+
+        // Perfectly even spacing is unreachable, but the spread is still improving.
+        assert!(matches!(
+            fibre.try_build_uniform(40, 0_f32, 3),
+            Err(FibreBuildError::NTriesExceed(3))
+        ));
+    }
+
+    /// Close to the pole f32 precision bounds the spread above the tolerance,
+    /// the most even try is returned rather than searching on.
+    #[test]
+    fn near_pole_settles_for_the_best_spacing() {
+        let alpha = 0_f32..=F32_4PI;
+        let cases = [(89_f32, 10), (89_f32, 40), (85_f32, 120), (80_f32, 400)];
+        for ((lat, n_points), lon) in cases.into_iter().zip([0_f32, 90_f32].repeat(2)) {
+            let fibre = Fibre::new(
+                SurfacePoint {
+                    lat: lat.to_radians(),
+                    lon: lon.to_radians(),
+                },
+                &alpha,
+            );
+            let (points, _) = fibre
+                .try_build_uniform(n_points, 1e-3, 2000)
+                .unwrap_or_else(|e| panic!("{lat} {lon} {n_points}: {e}"));
+            assert_eq!(points.len(), n_points);
+            assert!(points.iter().all(|v| v.0.is_finite()));
+        }
+    }
+
+    /// Rotating S3 can carry a fibre through the projection pole.
+    #[test]
+    fn pole_crossing_is_an_error() {
+        use crate::rotation::Plane;
+        use crate::rotation::plane_rotation;
+
+        let alpha = 0_f32..=F32_4PI;
+        for angle in [
+            core::f32::consts::FRAC_PI_4,
+            5_f32 * core::f32::consts::FRAC_PI_4,
+        ] {
+            let fibre = Fibre::new(
+                SurfacePoint {
+                    lat: 0_f32,
+                    lon: 0_f32,
+                },
+                &alpha,
+            )
+            .with_rotation(plane_rotation(Plane::YW, angle));
+            assert!(matches!(
+                fibre.try_build_uniform(40, 1e-3, 2000),
+                Err(FibreBuildError::NotFinite)
+            ));
+        }
+    }
 }
@@ -0,0 +1,519 @@
+use crate::Vertex;
+use crate::check_attribute_lens;
+use crate::mesh::Grid;
+use std::fmt::Write as _;
+use std::io::BufWriter;
+use std::io::Error;
+use std::io::Write;
+
+use glam::Vec3;
+
+// Component types, buffer view targets and primitive modes from the glTF 2.0 specification.
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const MODE_POINTS: u32 = 0;
+const MODE_LINES: u32 = 1;
+const MODE_TRIANGLES: u32 = 4;
+
+// GLB magic numbers, little endian "glTF", "JSON" and "BIN\0".
+const GLB_MAGIC: u32 = 0x4654_6C67;
+const GLB_JSON: u32 = 0x4E4F_534A;
+const GLB_BIN: u32 = 0x004E_4942;
+
+/// A named mesh, written as its own node.
+///
+/// Normals, colours and texture coordinates are optional, when present
+/// there must be one per position. Indices start at 0.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GltfMesh {
+    /// The name of the mesh, and of its node.
+    pub name: String,
+    /// Vertex positions.
+    pub positions: Vec<Vec3>,
+    /// Per vertex normals, or empty.
+    pub normals: Vec<Vec3>,
+    /// Per vertex linear RGB colours, or empty.
+    pub colors: Vec<[f32; 3]>,
+    /// Per vertex texture coordinates, or empty.
+    pub uvs: Vec<[f32; 2]>,
+    /// Triangles, counter clockwise when seen from the front.
+    pub triangles: Vec<[u32; 3]>,
+    /// Line segments, such as the steps along a fibre.
+    pub lines: Vec<[u32; 2]>,
+}
+
+impl GltfMesh {
+    /// A woven grid, with its normals and texture coordinates.
+    ///
+    /// Each quad is split into two triangles.
+    /// glTF requires unit normals, so where the grid folds onto itself and
+    /// a normal is undefined they are all left out, viewers then shade the
+    /// triangles flat.
+    ///
+    /// # Errors
+    ///   When the grid is too large to be indexed by a `u32`.
+    pub fn from_grid(name: String, grid: &Grid) -> Result<Self, Error> {
+        to_index(grid.vertices.len())?;
+        // Checked above, every index fits.
+        #[allow(clippy::cast_possible_truncation)]
+        let triangles = grid
+            .triangles()
+            .map(|triangle| triangle.map(|i| i as u32))
+            .collect();
+        let mut normals = grid.normals();
+        if normals.contains(&Vec3::ZERO) {
+            normals.clear();
+        }
+        Ok(Self {
+            name,
+            positions: grid.vertices.iter().map(|v| v.0).collect(),
+            normals,
+            uvs: grid.uvs(),
+            triangles,
+            ..Self::default()
+        })
+    }
+
+    /// A polyline, such as a fibre, as a chain of line segments.
+    ///
+    /// # Errors
+    ///   When the line is too long to be indexed by a `u32`.
+    pub fn from_polyline(name: String, line: &[Vertex]) -> Result<Self, Error> {
+        let n = to_index(line.len())?;
+        Ok(Self {
+            name,
+            positions: line.iter().map(|v| v.0).collect(),
+            lines: (1..n).map(|i| [i - 1, i]).collect(),
+            ..Self::default()
+        })
+    }
+
+    // Non empty, with attributes and indices matching the vertices.
+    fn validate(&self) -> Result<(), Error> {
+        let n = self.positions.len();
+        if n == 0 {
+            return Err(Error::other(format!(
+                "Cannot write the glTF mesh {:?}, it has no vertices.",
+                self.name
+            )));
+        }
+        to_index(n)?;
+        check_attribute_lens(
+            &format!("the glTF mesh {:?}", self.name),
+            n,
+            &[
+                ("normals", self.normals.len()),
+                ("colors", self.colors.len()),
+                ("uvs", self.uvs.len()),
+            ],
+        )?;
+        if !self.positions.iter().all(|p| p.is_finite()) {
+            return Err(Error::other(format!(
+                "Cannot write the glTF mesh {:?}, a position is not finite.",
+                self.name
+            )));
+        }
+        if let Some(i) = self
+            .triangles
+            .iter()
+            .flatten()
+            .chain(self.lines.iter().flatten())
+            .find(|i| **i as usize >= n)
+        {
+            return Err(Error::other(format!(
+                "Cannot write the glTF mesh {:?}, index {i} is out of range for {n} vertices.",
+                self.name
+            )));
+        }
+        Ok(())
+    }
+}
+
+// Indices are written as an `UNSIGNED_INT`.
+fn to_index(i: usize) -> Result<u32, Error> {
+    u32::try_from(i).map_err(|_| Error::other(format!("Cannot index vertex {i} in a glTF file.")))
+}
+
+// Quotes a string for JSON.
+fn json_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if u32::from(c) < 0x20 => {
+                let _ = write!(quoted, "\\u{:04x}", u32::from(c));
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+// Formats a list of floats as a JSON array.
+fn json_floats(values: &[f32]) -> String {
+    let values = values.iter().map(ToString::to_string).collect::<Vec<_>>();
+    format!("[{}]", values.join(","))
+}
+
+// Accumulates the binary buffer, along with the views and accessors into it.
+#[derive(Default)]
+struct Packer {
+    bin: Vec<u8>,
+    views: Vec<String>,
+    accessors: Vec<String>,
+}
+
+impl Packer {
+    // Appends `data` as a buffer view, returning the index of its accessor.
+    fn push(
+        &mut self,
+        data: &[u8],
+        target: u32,
+        component: u32,
+        count: usize,
+        kind: &str,
+        bounds: Option<(Vec3, Vec3)>,
+    ) -> usize {
+        // Every component is 4 bytes wide, so views stay aligned.
+        let offset = self.bin.len();
+        self.bin.extend_from_slice(data);
+        self.views.push(format!(
+            "{{\"buffer\":0,\"byteOffset\":{offset},\"byteLength\":{},\"target\":{target}}}",
+            data.len()
+        ));
+        let bounds = bounds.map_or_else(String::new, |(min, max)| {
+            format!(
+                ",\"min\":{},\"max\":{}",
+                json_floats(&min.to_array()),
+                json_floats(&max.to_array())
+            )
+        });
+        self.accessors.push(format!(
+            "{{\"bufferView\":{},\"componentType\":{component},\"count\":{count},\"type\":\"{kind}\"{bounds}}}",
+            self.views.len() - 1
+        ));
+        self.accessors.len() - 1
+    }
+
+    // Appends the attributes and indices of a mesh, returning its primitives.
+    fn push_mesh(&mut self, mesh: &GltfMesh) -> Vec<String> {
+        let n = mesh.positions.len();
+        let (min, max) = mesh
+            .positions
+            .iter()
+            .fold((Vec3::INFINITY, Vec3::NEG_INFINITY), |(min, max), p| {
+                (min.min(*p), max.max(*p))
+            });
+        let mut attributes = vec![format!(
+            "\"POSITION\":{}",
+            self.push(
+                bytemuck::cast_slice(&mesh.positions),
+                ARRAY_BUFFER,
+                FLOAT,
+                n,
+                "VEC3",
+                Some((min, max)),
+            )
+        )];
+        if !mesh.normals.is_empty() {
+            let normals = self.push(
+                bytemuck::cast_slice(&mesh.normals),
+                ARRAY_BUFFER,
+                FLOAT,
+                n,
+                "VEC3",
+                None,
+            );
+            attributes.push(format!("\"NORMAL\":{normals}"));
+        }
+        if !mesh.uvs.is_empty() {
+            let uvs = self.push(
+                bytemuck::cast_slice(&mesh.uvs),
+                ARRAY_BUFFER,
+                FLOAT,
+                n,
+                "VEC2",
+                None,
+            );
+            attributes.push(format!("\"TEXCOORD_0\":{uvs}"));
+        }
+        if !mesh.colors.is_empty() {
+            let colors = self.push(
+                bytemuck::cast_slice(&mesh.colors),
+                ARRAY_BUFFER,
+                FLOAT,
+                n,
+                "VEC3",
+                None,
+            );
+            attributes.push(format!("\"COLOR_0\":{colors}"));
+        }
+        let attributes = attributes.join(",");
+
+        let mut primitives = vec![];
+        for (indices, mode) in [
+            (
+                bytemuck::cast_slice::<_, u32>(&mesh.triangles),
+                MODE_TRIANGLES,
+            ),
+            (bytemuck::cast_slice::<_, u32>(&mesh.lines), MODE_LINES),
+        ] {
+            if indices.is_empty() {
+                continue;
+            }
+            let accessor = self.push(
+                bytemuck::cast_slice(indices),
+                ELEMENT_ARRAY_BUFFER,
+                UNSIGNED_INT,
+                indices.len(),
+                "SCALAR",
+                None,
+            );
+            primitives.push(format!(
+                "{{\"attributes\":{{{attributes}}},\"indices\":{accessor},\"mode\":{mode}}}"
+            ));
+        }
+        // Without faces or lines, the vertices are drawn as a point cloud.
+        if primitives.is_empty() {
+            primitives.push(format!(
+                "{{\"attributes\":{{{attributes}}},\"mode\":{MODE_POINTS}}}"
+            ));
+        }
+
+        primitives
+    }
+}
+
+/// Hold state information related to the storage of
+/// meshes in a glTF 2.0 file.
+///
+/// Each mesh becomes a named node of the default scene, with a triangle and
+/// a line primitive sharing its vertex attributes. Written either as a
+/// `.gltf` with a separate `.bin` buffer, or as a single `.glb`.
+#[derive(Debug, Default, PartialEq)]
+pub struct Gltf {
+    /// The meshes, in node order.
+    pub meshes: Vec<GltfMesh>,
+}
+
+impl Gltf {
+    /// Appends a woven grid as a named mesh, see [`GltfMesh::from_grid`].
+    ///
+    /// # Errors
+    ///   When the grid is too large to be indexed by a `u32`.
+    pub fn push_grid(&mut self, name: String, grid: &Grid) -> Result<(), Error> {
+        self.meshes.push(GltfMesh::from_grid(name, grid)?);
+        Ok(())
+    }
+
+    /// Appends a polyline as a named mesh, see [`GltfMesh::from_polyline`].
+    ///
+    /// # Errors
+    ///   When the line is too long to be indexed by a `u32`.
+    pub fn push_polyline(&mut self, name: String, line: &[Vertex]) -> Result<(), Error> {
+        self.meshes.push(GltfMesh::from_polyline(name, line)?);
+        Ok(())
+    }
+
+    // The JSON document, and the binary buffer it describes.
+    fn pack(&self, uri: Option<&str>) -> Result<(String, Vec<u8>), Error> {
+        let mut packer = Packer::default();
+        let mut meshes = Vec::with_capacity(self.meshes.len());
+        let mut nodes = Vec::with_capacity(self.meshes.len());
+        for (i, mesh) in self.meshes.iter().enumerate() {
+            mesh.validate()?;
+            let primitives = packer.push_mesh(mesh);
+            let name = json_string(&mesh.name);
+            meshes.push(format!(
+                "{{\"name\":{name},\"primitives\":[{}]}}",
+                primitives.join(",")
+            ));
+            nodes.push(format!("{{\"name\":{name},\"mesh\":{i}}}"));
+        }
+
+        let uri = uri.map_or_else(String::new, |uri| format!(",\"uri\":{}", json_string(uri)));
+        let scene = (0..nodes.len())
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        let json = format!(
+            "{{\"asset\":{{\"version\":\"2.0\",\"generator\":\"hopf\"}},\
+             \"scene\":0,\"scenes\":[{{\"nodes\":[{scene}]}}],\
+             \"nodes\":[{}],\"meshes\":[{}],\
+             \"accessors\":[{}],\"bufferViews\":[{}],\
+             \"buffers\":[{{\"byteLength\":{}{uri}}}]}}",
+            nodes.join(","),
+            meshes.join(","),
+            packer.accessors.join(","),
+            packer.views.join(","),
+            packer.bin.len(),
+        );
+        Ok((json, packer.bin))
+    }
+
+    /// Writes a `.gltf` document to `out`, and its buffer to `bin`.
+    ///
+    /// `bin_uri` is the location of the buffer, relative to the document.
+    ///
+    /// # Errors
+    ///   When writing to a buffer fails, or a mesh is malformed.
+    pub fn write_gltf<W, B>(
+        &self,
+        bin_uri: &str,
+        out: &mut BufWriter<W>,
+        bin: &mut BufWriter<B>,
+    ) -> Result<(), Error>
+    where
+        W: ?Sized + std::io::Write,
+        B: ?Sized + std::io::Write,
+    {
+        let (json, buffer) = self.pack(Some(bin_uri))?;
+        out.write_all(json.as_bytes())?;
+        bin.write_all(&buffer)
+    }
+
+    /// Writes a single binary `.glb` file.
+    ///
+    /// # Errors
+    ///   When writing to a buffer fails, or a mesh is malformed.
+    pub fn write_glb<W>(&self, out: &mut BufWriter<W>) -> Result<(), Error>
+    where
+        W: ?Sized + std::io::Write,
+    {
+        let (json, mut buffer) = self.pack(None)?;
+        // Chunks are padded to 4 bytes, JSON with spaces.
+        let mut json = json.into_bytes();
+        json.resize(json.len().next_multiple_of(4), b' ');
+        buffer.resize(buffer.len().next_multiple_of(4), 0);
+
+        let length = 12 + 8 + json.len() + 8 + buffer.len();
+        let length = u32::try_from(length)
+            .map_err(|_| Error::other("Cannot write a GLB file larger than 4 GiB."))?;
+        // Both chunks are smaller than the whole.
+        #[allow(clippy::cast_possible_truncation)]
+        let chunk = |len: usize, kind: u32| [(len as u32).to_le_bytes(), kind.to_le_bytes()];
+
+        for word in [GLB_MAGIC, 2, length] {
+            out.write_all(&word.to_le_bytes())?;
+        }
+        out.write_all(chunk(json.len(), GLB_JSON).as_flattened())?;
+        out.write_all(&json)?;
+        out.write_all(chunk(buffer.len(), GLB_BIN).as_flattened())?;
+        out.write_all(&buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn le_u32(bytes: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+    }
+
+    fn sheet() -> Grid {
+        let mut grid = Grid::new(3);
+        grid.push_loop(&[Vertex(Vec3::ZERO), Vertex(Vec3::X), Vertex(2_f32 * Vec3::X)]);
+        grid.push_loop(&[
+            Vertex(Vec3::Z),
+            Vertex(Vec3::X + Vec3::Z),
+            Vertex(Vec3::ONE),
+        ]);
+        grid
+    }
+
+    #[test]
+    fn glb_layout() {
+        let mut gltf = Gltf::default();
+        gltf.push_grid("outer \"shell\"".to_string(), &sheet())
+            .unwrap();
+        gltf.push_polyline("fibre_0".to_string(), &[Vertex(Vec3::Y), Vertex(Vec3::ONE)])
+            .unwrap();
+        let mut out = BufWriter::new(vec![]);
+        gltf.write_glb(&mut out).unwrap();
+        let glb = out.into_inner().unwrap();
+
+        assert_eq!(&glb[..4], b"glTF");
+        assert_eq!(le_u32(&glb, 4), 2);
+        assert_eq!(le_u32(&glb, 8) as usize, glb.len());
+        let json_len = le_u32(&glb, 12) as usize;
+        assert_eq!(&glb[16..20], b"JSON");
+        assert_eq!(json_len % 4, 0);
+        let json = std::str::from_utf8(&glb[20..20 + json_len]).unwrap();
+        let bin_at = 20 + json_len;
+        assert_eq!(&glb[bin_at + 4..bin_at + 8], b"BIN\0");
+        let bin = &glb[bin_at + 8..];
+        assert_eq!(le_u32(&glb, bin_at) as usize, bin.len());
+
+        assert!(json.contains(r#""name":"outer \"shell\"""#));
+        assert!(json.contains(r#""nodes":[0,1]"#));
+        assert!(json.contains(r#""min":[0,0,0],"max":[2,1,1]"#));
+        assert!(json.contains(r#""NORMAL":1,"TEXCOORD_0":2"#));
+        // Two quads as four triangles, and one line segment.
+        assert!(json.contains(r#""count":12,"type":"SCALAR""#));
+        assert!(json.contains(r#""indices":5,"mode":1"#));
+        // The first position of the second mesh follows the sheet's buffers.
+        let positions = bytemuck::cast_slice::<u8, f32>(&bin[..4 * 3 * 6]);
+        assert_eq!(positions.len(), 18);
+        assert!(
+            positions[3..6]
+                .iter()
+                .zip([1_f32, 0_f32, 0_f32])
+                .all(|(a, b)| (a - b).abs() < 1e-6)
+        );
+    }
+
+    #[test]
+    fn undefined_normals_are_left_out() {
+        let mesh = GltfMesh::from_grid("sheet".to_string(), &sheet()).unwrap();
+        assert_eq!(mesh.normals.len(), 6);
+
+        // A sheet folded flat onto a line.
+        let mut folded = Grid::new(3);
+        for _ in 0..2 {
+            folded.push_loop(&[Vertex(Vec3::ZERO), Vertex(Vec3::X), Vertex(2_f32 * Vec3::X)]);
+        }
+        let mesh = GltfMesh::from_grid("folded".to_string(), &folded).unwrap();
+        assert!(mesh.normals.is_empty());
+        assert_eq!(mesh.triangles.len(), 4);
+    }
+
+    #[test]
+    fn gltf_references_its_buffer() {
+        let mut gltf = Gltf::default();
+        gltf.push_grid("sheet".to_string(), &sheet()).unwrap();
+        gltf.meshes[0].colors = vec![[1_f32, 0_f32, 0_f32]; 6];
+        let mut out = BufWriter::new(vec![]);
+        let mut bin = BufWriter::new(vec![]);
+        gltf.write_gltf("sheet.bin", &mut out, &mut bin).unwrap();
+        let json = String::from_utf8(out.into_inner().unwrap()).unwrap();
+        let bin = bin.into_inner().unwrap();
+
+        assert!(json.contains(&format!(r#""byteLength":{},"uri":"sheet.bin""#, bin.len())));
+        assert!(json.contains(r#""COLOR_0":3"#));
+        // Positions, normals and colours of 6 vertices, uvs and 12 indices.
+        assert_eq!(bin.len(), 6 * 3 * 4 * 3 + 6 * 2 * 4 + 12 * 4);
+    }
+
+    #[test]
+    fn rejects_malformed_meshes() {
+        let mut gltf = Gltf::default();
+        gltf.push_grid("sheet".to_string(), &sheet()).unwrap();
+        gltf.meshes[0].normals.pop();
+        assert!(gltf.write_glb(&mut BufWriter::new(vec![])).is_err());
+
+        let gltf = Gltf {
+            meshes: vec![GltfMesh {
+                positions: vec![Vec3::ZERO],
+                lines: vec![[0, 1]],
+                ..GltfMesh::default()
+            }],
+        };
+        assert!(gltf.write_glb(&mut BufWriter::new(vec![])).is_err());
+    }
+}
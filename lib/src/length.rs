@@ -56,6 +56,72 @@ pub(crate) fn searchable_path_length<const N_POINTS_PER_LOOP: usize>(
     })
 }
 
+// Runtime sized version of `searchable_path_length()`.
+//
+// Unlike the array version the last sample lies on the end of
+// the range, return [(alpha, dist); n_detailed]
+//
+// The distance is accumulated in f64. In f32 the rounding error of
+// summing many tiny steps grows with the resolution of the LUT.
+#[allow(clippy::cast_precision_loss)]
+pub(crate) fn path_length_lut(
+    fibre: impl Fn(f32) -> Vertex,
+    alpha_range: &RangeInclusive<f32>,
+    n_detailed: usize,
+) -> Vec<(f32, f64)> {
+    debug_assert!(n_detailed > 1);
+    let alpha_start = f64::from(*alpha_range.start());
+    let alpha_step = (f64::from(*alpha_range.end()) - alpha_start) / (n_detailed - 1) as f64;
+
+    let mut f_last = fibre(*alpha_range.start()).0.as_dvec3();
+    let mut d = 0_f64;
+    (0..n_detailed)
+        .map(|i| {
+            #[allow(clippy::cast_possible_truncation)]
+            let alpha = (i as f64).mul_add(alpha_step, alpha_start) as f32;
+            let f = fibre(alpha).0.as_dvec3();
+            d += f.distance(f_last);
+            f_last = f;
+            (alpha, d)
+        })
+        .collect()
+}
+
+// Reparameterize, returning `n_points` alpha values evenly spaced by path length.
+//
+// Alpha is linearly interpolated between the entries of the LUT.
+#[allow(clippy::cast_precision_loss)]
+#[allow(clippy::cast_possible_truncation)]
+pub(crate) fn resample_lut(lut: &[(f32, f64)], n_points: usize) -> Vec<f32> {
+    let path_length = lut.last().map_or(0_f64, |(_, d)| *d);
+    let step = if n_points > 1 {
+        path_length / (n_points - 1) as f64
+    } else {
+        0_f64
+    };
+
+    (0..n_points)
+        .map(|i| {
+            let dist_threshold = i as f64 * step;
+            let k = lut.partition_point(|&(_, d)| d < dist_threshold);
+            if k == 0 {
+                lut[0].0
+            } else if k == lut.len() {
+                lut[k - 1].0
+            } else {
+                let (a0, d0) = lut[k - 1];
+                let (a1, d1) = lut[k];
+                if d1 > d0 {
+                    let t = (dist_threshold - d0) / (d1 - d0);
+                    t.mul_add(f64::from(a1) - f64::from(a0), f64::from(a0)) as f32
+                } else {
+                    a0
+                }
+            }
+        })
+        .collect()
+}
+
 // Returns a coarse set of (alpha, distance) values
 // computed from fine grained sampling.
 pub(crate) fn resample_fibre<const N_DETAILED: usize, const N_COARSE: usize>(
@@ -134,6 +200,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn resampled_quarter_circle() {
+        let lut = path_length_lut(circle, &(0_f32..=f32::consts::TAU), 4096);
+        let alphas = resample_lut(&lut, 5);
+        let expected = [
+            0_f32,
+            f32::consts::FRAC_PI_2,
+            f32::consts::PI,
+            3_f32 * f32::consts::FRAC_PI_2,
+            f32::consts::TAU,
+        ];
+        for (alpha, expected) in alphas.iter().zip(expected) {
+            assert!(
+                (alpha - expected).abs() < 1e-4,
+                "alpha {alpha} expected {expected}"
+            );
+        }
+    }
+
     // Use a unit circle to confirm points are searchable.
     #[test]
     fn searchable() {
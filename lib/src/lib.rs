@@ -17,13 +17,34 @@ pub mod length;
 /// Collection of fibres woven into a mesh.
 pub mod mesh;
 
+/// Rotations of S3, applied before the stereographic projection.
+pub mod rotation;
+
 /// Surface point.
 pub mod sp;
 
+/// Tolerance based vertex welding.
+pub mod weld;
+
 // /// A Point Cloud
 /// Handling OBJ file format.
 pub mod obj;
 
+/// Handling PLY file format.
+pub mod ply;
+
+/// Handling glTF 2.0 file format.
+pub mod gltf;
+
+/// Handling STL file format, for watertight meshes.
+pub mod stl;
+
+/// Handling SVG file format, for 2D figures of fibres.
+pub mod svg;
+
+/// Comparing generated models within a tolerance.
+pub mod diff;
+
 use std::hash::Hash;
 use std::hash::Hasher;
 use std::io::{BufWriter, Write};
@@ -115,15 +136,15 @@ impl Vertex {
 
 /// Stereographic projection of a fibre onto the base space.
 ///
-/// # Panics
-///  If the point is at infinity or -infinity (X3 == 1)
+/// The projection pole (X3 == 1) has no image, there the vertex is NaN.
+/// Rotations of S3 can move a fibre through the pole, so callers must
+/// expect non finite vertices.
 #[must_use = "Not using the returned, will drop the computation."]
 #[allow(non_snake_case)]
 pub fn project(X0: f32, X1: f32, X2: f32, X3: f32) -> Vertex {
     if (1_f32 - X3).abs() < f32::EPSILON {
-        // Handle the case where the point is at infinity or -infinity.
-        // For now just stop.
-        panic!("division by zero");
+        // The point at infinity.
+        Vertex(Vec3::NAN)
     } else {
         let x = X0 / (1_f32 - X3);
         let y = X1 / (1_f32 - X3);
@@ -134,6 +155,10 @@ pub fn project(X0: f32, X1: f32, X2: f32, X3: f32) -> Vertex {
 
 /// Generate a PLY file from a `PointCloud`.
 ///
+/// An ASCII list of vertices, streamed as the points are produced.
+/// See [`ply::write_point_cloud`] for binary output, and [`ply::Ply`] for
+/// faces and edges.
+///
 /// # Errors
 ///   When writing to a buffer fails
 pub fn generate_ply<I, W>(points: I, out: &mut BufWriter<W>) -> Result<(), std::io::Error>
@@ -159,6 +184,8 @@ where
 
 /// Each fibre becomes a "line" in a OBJ file
 ///
+/// The fibres are named `fibre_0`, `fibre_1` ..., see [`obj::Obj::push_polyline`]
+/// to mix fibres with surfaces in one file.
 ///
 /// # Errors
 ///   When writing to a buffer fails
@@ -169,20 +196,28 @@ pub fn generate_obj_lines<W>(
 where
     W: ?Sized + std::io::Write,
 {
-    // in OBJ files the index runs to 1...=N
-    let mut index = 1;
+    let mut obj = obj::Obj::default();
     for (i, line) in lines_gen.iter().enumerate() {
-        writeln!(out, "o fibre_{i}")?;
-        for Vertex(Vec3 { x, y, z }) in line {
-            writeln!(out, "v {x} {y} {z}")?;
-        }
-        write!(out, "l")?;
-
-        for _ in line {
-            write!(out, " {index}")?;
-            index += 1;
-        }
-        writeln!(out)?;
+        obj.push_polyline(&format!("fibre_{i}"), line);
+    }
+    obj.write(out)
+}
+
+// Per vertex attributes must cover every vertex, or be absent.
+//
+// `target` completes "Cannot write ..", as in "an OBJ file".
+pub(crate) fn check_attribute_lens(
+    target: &str,
+    n_vertices: usize,
+    attributes: &[(&str, usize)],
+) -> Result<(), std::io::Error> {
+    match attributes
+        .iter()
+        .find(|(_, len)| *len != 0 && *len != n_vertices)
+    {
+        Some((name, len)) => Err(std::io::Error::other(format!(
+            "Cannot write {target} with {len} {name} for {n_vertices} vertices."
+        ))),
+        None => Ok(()),
     }
-    Ok(())
 }
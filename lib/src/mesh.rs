@@ -1,5 +1,13 @@
 //! Collections of fibres woven into a mesh.
 
+use core::error::Error;
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+use glam::Quat;
+use glam::Vec3;
+
+use super::Vertex;
 use super::sp::SurfacePoint;
 
 /// For a line segment of s2 ( as defined by two points on the globe ) divide
@@ -24,3 +32,557 @@ pub fn weave<'a>(
         SurfacePoint { lat, lon }
     })
 }
+
+/// As [`weave`], but the seed points follow the great circle from `p1` towards `p2`.
+///
+/// Unlike a linear interpolation of lat/lon this is the shortest path on s2.
+pub fn weave_geodesic<'a>(
+    p1: &'a SurfacePoint,
+    p2: &'a SurfacePoint,
+    n_loops: u16,
+) -> impl Iterator<Item = SurfacePoint> + 'a {
+    let a = p1.to_cartesian(1_f32);
+    let b = p2.to_cartesian(1_f32);
+    let rotation = Quat::from_rotation_arc(a, b);
+
+    (0..n_loops).map(move |index| {
+        let t = f32::from(index) / f32::from(n_loops);
+        SurfacePoint::from_cartesian(Quat::IDENTITY.slerp(rotation, t) * a)
+    })
+}
+
+/// A tube of `radius` around `path`, as a grid of rings.
+///
+/// Each ring holds `n_sides + 1` points, the first point is repeated to close the ring.
+/// Frames are parallel transported along the path, for a closed path ( where the
+/// last point repeats the first ) the twist
+/// is spread along the tube so the ends meet.
+///
+/// # Panics
+///   When the path has fewer than two points.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn tube(path: &[Vertex], radius: f32, n_sides: u16) -> Grid {
+    assert!(path.len() >= 2, "a tube needs at least two points");
+    let points = path.iter().map(|v| v.0).collect::<Vec<_>>();
+    let n = points.len();
+    // Closed when the ends are much closer than neighbouring points.
+    let closed = n > 2 && points[0].distance(points[n - 1]) < 0.5 * points[0].distance(points[1]);
+
+    let tangents = (0..n)
+        .map(|i| {
+            let (prev, next) = match i {
+                0 if closed => (points[n - 2], points[1]),
+                0 => (points[0], points[1]),
+                i if i == n - 1 && closed => (points[n - 2], points[1]),
+                i if i == n - 1 => (points[n - 2], points[n - 1]),
+                i => (points[i - 1], points[i + 1]),
+            };
+            (next - prev).normalize_or(Vec3::X)
+        })
+        .collect::<Vec<_>>();
+
+    let mut normals = Vec::with_capacity(n);
+    normals.push(tangents[0].any_orthonormal_vector());
+    for i in 1..n {
+        let transported = Quat::from_rotation_arc(tangents[i - 1], tangents[i]) * normals[i - 1];
+        // Re-orthogonalise against drift.
+        let normal = transported.reject_from_normalized(tangents[i]);
+        normals.push(normal.normalize_or(tangents[i].any_orthonormal_vector()));
+    }
+
+    if closed {
+        // The angle the frame has turned, after one trip around the loop.
+        let first = normals[0];
+        let last = normals[n - 1];
+        let twist = tangents[0].dot(last.cross(first)).atan2(last.dot(first));
+        for (i, (normal, tangent)) in normals.iter_mut().zip(&tangents).enumerate() {
+            let share = twist * i as f32 / (n - 1) as f32;
+            *normal = Quat::from_axis_angle(*tangent, share) * *normal;
+        }
+    }
+
+    let mut grid = Grid::new(usize::from(n_sides) + 1);
+    for ((p, tangent), normal) in points.iter().zip(&tangents).zip(&normals) {
+        let binormal = tangent.cross(*normal);
+        let ring = (0..=n_sides)
+            .map(|side| {
+                let theta = core::f32::consts::TAU * f32::from(side) / f32::from(n_sides);
+                let (sin, cos) = theta.sin_cos();
+                Vertex(*p + radius * (cos * *normal + sin * binormal))
+            })
+            .collect::<Vec<_>>();
+        grid.push_loop(&ring);
+    }
+    grid
+}
+
+// As in `tube`, closed when the ends are much closer than neighbouring points.
+fn ends_meet(first: Vec3, second: Vec3, last: Vec3) -> bool {
+    first.distance(last) < 0.5 * first.distance(second)
+}
+
+// Every `step`th index below `n`, always including the last.
+fn strided(n: usize, step: usize) -> Vec<usize> {
+    let mut indices = (0..n).step_by(step.max(1)).collect::<Vec<_>>();
+    if n > 0 && indices.last() != Some(&(n - 1)) {
+        indices.push(n - 1);
+    }
+    indices
+}
+
+/// Why a grid cannot be thickened, see [`Grid::solidify`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SolidifyError {
+    /// The normal at this index into `vertices` is undefined,
+    /// its neighbourhood is degenerate.
+    ZeroNormal(usize),
+}
+
+impl Error for SolidifyError {}
+
+impl Display for SolidifyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ZeroNormal(i) => {
+                write!(
+                    f,
+                    "the normal at vertex {i} is undefined, it cannot be offset"
+                )
+            }
+        }
+    }
+}
+
+/// A woven mesh is a regular grid, `n_loops` x `n_points_per_loop`.
+///
+/// Vertices are stored loop by loop, so the topology is implied by the
+/// grid coordinates and no hashing/deduplication is needed.
+#[derive(Clone, Debug)]
+pub struct Grid {
+    /// Vertices stored loop by loop.
+    pub vertices: Vec<Vertex>,
+    n_points_per_loop: usize,
+}
+
+impl Grid {
+    /// Creates an empty grid, where every loop holds `n_points_per_loop` points.
+    #[must_use]
+    pub const fn new(n_points_per_loop: usize) -> Self {
+        Self {
+            vertices: vec![],
+            n_points_per_loop,
+        }
+    }
+
+    /// Append the next loop to the grid.
+    ///
+    /// # Panics
+    ///   When the number of points differs from `n_points_per_loop`.
+    pub fn push_loop(&mut self, points: &[Vertex]) {
+        assert_eq!(points.len(), self.n_points_per_loop);
+        self.vertices.extend_from_slice(points);
+    }
+
+    /// The number of loops pushed so far.
+    #[must_use]
+    pub fn n_loops(&self) -> usize {
+        self.vertices
+            .len()
+            .checked_div(self.n_points_per_loop)
+            .unwrap_or(0)
+    }
+
+    /// The number of points in each loop.
+    #[must_use]
+    pub const fn n_points_per_loop(&self) -> usize {
+        self.n_points_per_loop
+    }
+
+    /// Index into `vertices` of the point `i_point` on loop `i_loop`.
+    #[inline]
+    #[must_use]
+    pub const fn index(&self, i_loop: usize, i_point: usize) -> usize {
+        i_loop * self.n_points_per_loop + i_point
+    }
+
+    /// A coarser grid, keeping every `loop_step`th loop and every `point_step`th point.
+    ///
+    /// The first and last loops ( and points ) are always kept, so the
+    /// extent of the grid is preserved and closed loops stay closed.
+    /// Also returns the index into `vertices` of each kept vertex,
+    /// so data stored alongside the vertices can follow.
+    #[must_use]
+    pub fn subsample(&self, loop_step: usize, point_step: usize) -> (Self, Vec<usize>) {
+        let loops = strided(self.n_loops(), loop_step);
+        let points = strided(self.n_points_per_loop, point_step);
+        let kept = loops
+            .iter()
+            .flat_map(|l| points.iter().map(move |i| self.index(*l, *i)))
+            .collect::<Vec<_>>();
+        let grid = Self {
+            vertices: kept.iter().map(|i| self.vertices[*i]).collect(),
+            n_points_per_loop: points.len(),
+        };
+        (grid, kept)
+    }
+
+    /// Texture coordinates of each vertex.
+    ///
+    /// u runs along each loop, v across the loops, both from 0 to 1.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn uvs(&self) -> Vec<[f32; 2]> {
+        let u_max = (self.n_points_per_loop.max(2) - 1) as f32;
+        let v_max = (self.n_loops().max(2) - 1) as f32;
+        (0..self.n_loops())
+            .flat_map(|l| {
+                let v = l as f32 / v_max;
+                (0..self.n_points_per_loop).map(move |i| [i as f32 / u_max, v])
+            })
+            .collect()
+    }
+
+    /// Smooth normals of each vertex, facing the same way as [`Grid::quads`].
+    ///
+    /// Estimated by central differences along and across the loops,
+    /// a loop ( or column ) whose ends meet wraps around the seam.
+    #[must_use]
+    pub fn normals(&self) -> Vec<Vec3> {
+        let n_loops = self.n_loops();
+        let n_points = self.n_points_per_loop;
+        let at = |l: usize, i: usize| Vec3::from(self.vertices[self.index(l, i)]);
+        // Neighbours of `i` in a run of `n`, wrapping when `closed`.
+        let neighbours = |i: usize, n: usize, closed: bool| match (i, closed) {
+            (0, true) => (n - 2, 1),
+            (i, true) if i == n - 1 => (n - 2, 1),
+            (i, _) => (i.saturating_sub(1), (i + 1).min(n - 1)),
+        };
+
+        let mut normals = Vec::with_capacity(self.vertices.len());
+        for l in 0..n_loops {
+            for i in 0..n_points {
+                let loop_closed =
+                    n_points > 2 && ends_meet(at(l, 0), at(l, 1), at(l, n_points - 1));
+                let (i0, i1) = neighbours(i, n_points, loop_closed);
+                let column_closed =
+                    n_loops > 2 && ends_meet(at(0, i), at(1, i), at(n_loops - 1, i));
+                let (l0, l1) = neighbours(l, n_loops, column_closed);
+                let du = at(l, i1) - at(l, i0);
+                let dv = at(l1, i) - at(l0, i);
+                normals.push(du.cross(dv).normalize_or_zero());
+            }
+        }
+        normals
+    }
+
+    /// A closed shell of `thickness` model units, as grids sharing their edges.
+    ///
+    /// The surface is offset half the thickness either way along [`Grid::normals`],
+    /// the outer layer keeps the winding of [`Grid::quads`] and the inner layer is
+    /// reversed. Each open boundary gets a side wall, a grid of two loops joining
+    /// the layers, so once welded the shell is manifold and faces outward.
+    /// Boundaries whose ends meet ( as for a tube ) need no wall.
+    ///
+    /// The grid knows nothing of the parametrisation it samples, so the normals
+    /// are estimated from neighbouring points rather than derived analytically.
+    ///
+    /// Also returns the index into `vertices` of each vertex, as [`Grid::subsample`].
+    ///
+    /// # Errors
+    ///   `SolidifyError::ZeroNormal` where a degenerate neighbourhood leaves the
+    ///   normal undefined, as the layers would pinch together there.
+    pub fn solidify(&self, thickness: f32) -> Result<Vec<(Self, Vec<usize>)>, SolidifyError> {
+        let n_loops = self.n_loops();
+        let n_points = self.n_points_per_loop;
+        if n_loops < 2 || n_points < 2 {
+            return Ok(vec![]);
+        }
+        let normals = self.normals();
+        if let Some(i) = normals.iter().position(|n| *n == Vec3::ZERO) {
+            return Err(SolidifyError::ZeroNormal(i));
+        }
+        let half = thickness / 2_f32;
+        // The points of `sources`, moved to one side of the surface.
+        let offset = |sources: &[usize], side: f32| {
+            sources
+                .iter()
+                .map(|i| Vertex(self.vertices[*i].0 + side * half * normals[*i]))
+                .collect::<Vec<_>>()
+        };
+        let layer = |sources: Vec<usize>, side: f32, n_points_per_loop: usize| {
+            let grid = Self {
+                vertices: offset(&sources, side),
+                n_points_per_loop,
+            };
+            (grid, sources)
+        };
+
+        let outer = (0..self.vertices.len()).collect::<Vec<_>>();
+        let inner = (0..n_loops)
+            .flat_map(|l| (0..n_points).rev().map(move |i| self.index(l, i)))
+            .collect::<Vec<_>>();
+        let mut shell = vec![
+            layer(outer, 1_f32, n_points),
+            layer(inner, -1_f32, n_points),
+        ];
+
+        let at = |l: usize, i: usize| self.vertices[self.index(l, i)].0;
+        let loops_closed = (0..n_loops)
+            .all(|l| n_points > 2 && ends_meet(at(l, 0), at(l, 1), at(l, n_points - 1)));
+        let columns_closed =
+            (0..n_points).all(|i| n_loops > 2 && ends_meet(at(0, i), at(1, i), at(n_loops - 1, i)));
+
+        // Each boundary, in the direction the outer quads run along it.
+        let mut boundaries = vec![];
+        if !columns_closed {
+            boundaries.push((0..n_points).map(|i| self.index(0, i)).collect::<Vec<_>>());
+        }
+        if !loops_closed {
+            boundaries.push((0..n_loops).map(|l| self.index(l, n_points - 1)).collect());
+        }
+        if !columns_closed {
+            boundaries.push(
+                (0..n_points)
+                    .rev()
+                    .map(|i| self.index(n_loops - 1, i))
+                    .collect(),
+            );
+        }
+        if !loops_closed {
+            boundaries.push((0..n_loops).rev().map(|l| self.index(l, 0)).collect());
+        }
+
+        // The wall runs inner to outer, so its quads cross each boundary edge
+        // in the opposite direction to the layers.
+        for boundary in boundaries {
+            let mut wall = Self::new(boundary.len());
+            wall.push_loop(&offset(&boundary, -1_f32));
+            wall.push_loop(&offset(&boundary, 1_f32));
+            let sources = boundary.iter().chain(&boundary).copied().collect();
+            shell.push((wall, sources));
+        }
+        Ok(shell)
+    }
+
+    /// Quads joining each loop to the next ( zero based indices ).
+    ///
+    /// ```text
+    ///  0 - 3
+    ///  | / |
+    ///  |/  |
+    ///  1 --2
+    /// ```
+    ///
+    /// Points 0 and 1 lie on the previous loop, 2 and 3 on the current loop.
+    pub fn quads(&self) -> impl Iterator<Item = [usize; 4]> + '_ {
+        (1..self.n_loops()).flat_map(move |l| {
+            (1..self.n_points_per_loop).map(move |i| {
+                [
+                    self.index(l - 1, i - 1),
+                    self.index(l - 1, i),
+                    self.index(l, i),
+                    self.index(l, i - 1),
+                ]
+            })
+        })
+    }
+
+    /// Each quad split into two triangles ( zero based indices ).
+    ///
+    /// ```text
+    ///  0 - 3
+    ///  | / |
+    ///  |/  |
+    ///  1 --2
+    /// ```
+    ///
+    /// Given a quad ( points 0, 1, 2, 3 )
+    /// form triangles (0,1,3) and (1,2,3), see [`Self::quads`].
+    pub fn triangles(&self) -> impl Iterator<Item = [usize; 3]> + '_ {
+        self.quads().flat_map(|[a, b, c, d]| [[a, b, d], [b, c, d]])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+
+    use super::*;
+
+    fn line(z: f32, n: u16) -> Vec<Vertex> {
+        (0..n)
+            .map(|i| Vertex(Vec3::new(f32::from(i), 0_f32, z)))
+            .collect()
+    }
+
+    #[test]
+    fn grid_topology() {
+        let mut grid = Grid::new(3);
+        grid.push_loop(&line(0_f32, 3));
+        grid.push_loop(&line(1_f32, 3));
+        grid.push_loop(&line(2_f32, 3));
+
+        assert_eq!(grid.n_loops(), 3);
+        assert_eq!(grid.vertices.len(), 9);
+
+        let quads = grid.quads().collect::<Vec<_>>();
+        assert_eq!(
+            quads,
+            vec![[0, 1, 4, 3], [1, 2, 5, 4], [3, 4, 7, 6], [4, 5, 8, 7]]
+        );
+    }
+
+    #[test]
+    fn triangles_split_each_quad() {
+        let mut grid = Grid::new(3);
+        grid.push_loop(&line(0_f32, 3));
+        grid.push_loop(&line(1_f32, 3));
+
+        let triangles = grid.triangles().collect::<Vec<_>>();
+        assert_eq!(triangles, vec![[0, 1, 3], [1, 4, 3], [1, 2, 4], [2, 5, 4]]);
+    }
+
+    #[test]
+    fn subsample_keeps_the_ends() {
+        let mut grid = Grid::new(6);
+        for z in 0..4_u8 {
+            grid.push_loop(&line(f32::from(z), 6));
+        }
+
+        let (coarse, kept) = grid.subsample(2, 2);
+        // Loops 0, 2, 3 and points 0, 2, 4, 5.
+        assert_eq!(coarse.n_loops(), 3);
+        assert_eq!(coarse.n_points_per_loop(), 4);
+        assert_eq!(&kept[..4], &[0, 2, 4, 5]);
+        assert_eq!(kept[11], grid.vertices.len() - 1);
+        assert_eq!(coarse.vertices[11], grid.vertices[23]);
+
+        let (same, _) = grid.subsample(1, 1);
+        assert_eq!(same.vertices, grid.vertices);
+    }
+
+    #[test]
+    fn flat_grid_attributes() {
+        let mut grid = Grid::new(3);
+        for z in 0..3_u8 {
+            grid.push_loop(&line(f32::from(z), 3));
+        }
+        // The quads run +x then +z, so face -y.
+        for normal in grid.normals() {
+            assert!(normal.abs_diff_eq(Vec3::NEG_Y, 1e-6), "{normal}");
+        }
+        let uvs = grid.uvs();
+        assert!(glam::Vec2::from(uvs[0]).abs_diff_eq(glam::Vec2::ZERO, 1e-6));
+        let uv = glam::Vec2::from(uvs[grid.index(1, 2)]);
+        assert!(uv.abs_diff_eq(glam::Vec2::new(1_f32, 0.5), 1e-6));
+    }
+
+    #[test]
+    fn tube_normals_wrap_the_seam() {
+        let path = [Vertex(Vec3::ZERO), Vertex(Vec3::Z), Vertex(2_f32 * Vec3::Z)];
+        let grid = tube(&path, 1_f32, 8);
+        let normals = grid.normals();
+        // Every normal is radial, including those on the repeated seam.
+        for (v, n) in grid.vertices.iter().zip(&normals) {
+            let radial = Vec3::new(v.0.x, v.0.y, 0_f32);
+            assert!(n.cross(radial).length() < 1e-5, "{n} {radial}");
+        }
+        assert!(normals[0].abs_diff_eq(normals[8], 1e-6));
+    }
+
+    #[test]
+    fn solidify_adds_walls_to_open_boundaries() {
+        let mut sheet = Grid::new(3);
+        for z in 0..4_u8 {
+            sheet.push_loop(&line(f32::from(z), 3));
+        }
+        let shell = sheet.solidify(0.5).unwrap();
+        // Two layers and four walls.
+        assert_eq!(shell.len(), 6);
+        let (outer, sources) = &shell[0];
+        assert_eq!(sources.len(), 12);
+        // The sheet faces -y, so the outer layer moves that way.
+        assert!(
+            outer.vertices[5]
+                .0
+                .abs_diff_eq(Vec3::new(2_f32, -0.25, 1_f32), 1e-6)
+        );
+        let (inner, sources) = &shell[1];
+        assert_eq!(sources[..3], [2, 1, 0]);
+        assert!(
+            inner.vertices[0]
+                .0
+                .abs_diff_eq(Vec3::new(2_f32, 0.25, 0_f32), 1e-6)
+        );
+        let (wall, sources) = &shell[2];
+        assert_eq!(wall.n_loops(), 2);
+        assert_eq!(sources, &[0, 1, 2, 0, 1, 2]);
+
+        // The seams of a tube are closed, only the ends need walls.
+        let path = [Vertex(Vec3::ZERO), Vertex(Vec3::Z), Vertex(2_f32 * Vec3::Z)];
+        assert_eq!(tube(&path, 1_f32, 8).solidify(0.1).unwrap().len(), 4);
+
+        // A sheet folded flat onto a line has no normals.
+        let mut folded = Grid::new(3);
+        for _ in 0..3 {
+            folded.push_loop(&line(0_f32, 3));
+        }
+        assert_eq!(
+            folded.solidify(0.5).unwrap_err(),
+            SolidifyError::ZeroNormal(0)
+        );
+    }
+
+    #[test]
+    fn geodesic_stays_on_the_great_circle() {
+        let p1 = SurfacePoint {
+            lat: 10_f32.to_radians(),
+            lon: 0_f32,
+        };
+        let p2 = SurfacePoint {
+            lat: 40_f32.to_radians(),
+            lon: 120_f32.to_radians(),
+        };
+        let normal = p1
+            .to_cartesian(1_f32)
+            .cross(p2.to_cartesian(1_f32))
+            .normalize();
+
+        let seeds = weave_geodesic(&p1, &p2, 8).collect::<Vec<_>>();
+        assert_eq!(seeds.len(), 8);
+        for sp in seeds {
+            assert!(sp.to_cartesian(1_f32).dot(normal).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn tube_around_a_closed_circle() {
+        let n = 33_u16;
+        let circle = (0..n)
+            .map(|i| {
+                let theta = core::f32::consts::TAU * f32::from(i) / f32::from(n - 1);
+                Vertex(Vec3::new(theta.cos(), theta.sin(), 0_f32))
+            })
+            .collect::<Vec<_>>();
+
+        let grid = tube(&circle, 0.1, 8);
+        assert_eq!(grid.n_loops(), usize::from(n));
+        assert_eq!(grid.n_points_per_loop(), 9);
+        // Every ring point lies at the tube radius from the circle.
+        for v in &grid.vertices {
+            let centre = Vec3::new(v.0.x, v.0.y, 0_f32).normalize();
+            assert!((v.0.distance(centre) - 0.1).abs() < 1e-4);
+        }
+        // The ends of a closed tube meet.
+        let last = grid.index(usize::from(n) - 1, 0);
+        assert!(grid.vertices[0].0.distance(grid.vertices[last].0) < 1e-4);
+    }
+
+    #[test]
+    fn single_loop_has_no_quads() {
+        let mut grid = Grid::new(4);
+        grid.push_loop(&line(0_f32, 4));
+        assert_eq!(grid.quads().count(), 0);
+    }
+}
@@ -1,87 +1,664 @@
 use crate::Vertex;
+use crate::check_attribute_lens;
+use crate::mesh::Grid;
+use crate::weld::weld;
+use std::io::BufWriter;
 use std::io::Write;
-use std::{collections::HashMap, io::BufWriter};
 
 use glam::Vec3;
 
+/// A named object in an [`Obj`], written as an `o` with a matching `g` group.
+///
+/// Indices are one based, as in the file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ObjPart {
+    /// The name of the object, and its group.
+    pub name: String,
+    /// The [`ObjMaterial`] applied with `usemtl`, if any.
+    pub material: Option<String>,
+    /// Quads, see [`Grid::quads`] for the winding.
+    pub quads: Vec<[usize; 4]>,
+    /// Triangles.
+    pub triangles: Vec<[usize; 3]>,
+    /// Polylines, written as `l` records.
+    pub lines: Vec<Vec<usize>>,
+}
+
+/// A material written to the companion `.mtl` file, see [`Obj::write_mtl`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ObjMaterial {
+    /// Name referenced by [`ObjPart::material`].
+    pub name: String,
+    /// Diffuse RGB colour, each channel from 0 to 1.
+    pub color: [f32; 3],
+}
+
 /// Hold state information related to the storage of
 /// quads in a OBJ file.
-#[derive(Debug)]
+///
+/// Normals and texture coordinates are optional, when present there must be
+/// one per vertex, and faces are written as `f v/vt/vn`.
+#[derive(Debug, Default)]
 pub struct Obj {
-    next_index: usize,
-    /// A deduplicated list of points which
-    /// will be sorted and copied into a vertex buffer.
-    pub vertex_store: HashMap<Vertex, usize>,
-    // /// all point that appear in a obj file.
-    // pub vertex_buffer: Vec<Vertex>,
-    /// A list of quads keyed by object name.
-    pub quad_store: HashMap<String, Vec<[usize; 4]>>,
-}
-
-impl Default for Obj {
-    fn default() -> Self {
-        Self {
-            // wavefront Obj file start at index 1.
-            next_index: 1,
-            vertex_store: HashMap::default(),
-            quad_store: HashMap::default(),
-        }
-    }
+    /// All points that appear in the obj file, in index order.
+    ///
+    /// NB wavefront Obj file indices start at 1,
+    /// so `vertex_buffer[0]` is referenced as index 1.
+    pub vertex_buffer: Vec<Vertex>,
+    /// Per vertex normals, or empty.
+    pub normals: Vec<Vec3>,
+    /// Per vertex texture coordinates, or empty.
+    pub uvs: Vec<[f32; 2]>,
+    /// The named objects, in the order they are written.
+    pub parts: Vec<ObjPart>,
+    /// Materials for the `.mtl` file.
+    pub materials: Vec<ObjMaterial>,
+    /// The `.mtl` file referenced by `mtllib`, if any.
+    pub mtllib: Option<String>,
 }
 
 impl Obj {
-    /// Add a point to the obj file.
-    /// If the point has been seen before it will be deduplicated.
-    /// and the exiting index into the vertex buffer will be returned.
+    /// Add a point to the obj file, returning its index.
+    ///
+    /// Points are not deduplicated, see [`Obj::weld`].
     pub fn add_vertex(&mut self, p: &Vertex) -> usize {
-        if let Some(v) = self.vertex_store.get(p) {
-            *v
-        } else {
-            // first time seeing this points
-            // add it to buffer and the store.
-            let index = self.next_index;
-            self.vertex_store.insert(*p, index);
-            self.next_index += 1;
-            index
+        self.vertex_buffer.push(*p);
+        // wavefront Obj file start at index 1.
+        self.vertex_buffer.len()
+    }
+
+    /// The named object, created if missing.
+    ///
+    /// Objects are written in the order they are first created.
+    pub fn part_mut(&mut self, name: &str) -> &mut ObjPart {
+        self.named_part(name)
+    }
+
+    // As `part_mut`, an owned name is moved into a new part rather than copied.
+    fn named_part<S>(&mut self, name: S) -> &mut ObjPart
+    where
+        S: AsRef<str> + Into<String>,
+    {
+        if let Some(i) = self
+            .parts
+            .iter()
+            .position(|part| part.name == name.as_ref())
+        {
+            return &mut self.parts[i];
         }
+        self.parts.push(ObjPart {
+            name: name.into(),
+            ..ObjPart::default()
+        });
+        let i = self.parts.len() - 1;
+        &mut self.parts[i]
+    }
+
+    /// The named object.
+    #[must_use]
+    pub fn part(&self, name: &str) -> Option<&ObjPart> {
+        self.parts.iter().find(|part| part.name == name)
     }
 
     /// Push a prepared list of quads into the OBJ.
+    ///
+    /// Pushing an existing name replaces its quads in place.
     pub fn push_quads(&mut self, name: String, quads: Vec<[usize; 4]>) {
-        self.quad_store.insert(name, quads);
+        self.named_part(name).quads = quads;
     }
 
-    /// Writes `vertex_buffer` and quad information out to file.
+    /// Push a prepared list of triangles into the OBJ.
     ///
-    /// # Errors
-    ///   When writing to a buffer fails
+    /// Pushing an existing name replaces its triangles in place.
+    pub fn push_triangles(&mut self, name: String, triangles: Vec<[usize; 3]>) {
+        self.named_part(name).triangles = triangles;
+    }
+
+    /// Append a polyline, of existing vertices, to the named object.
+    pub fn push_line(&mut self, name: &str, line: Vec<usize>) {
+        self.part_mut(name).lines.push(line);
+    }
+
+    /// The quads of the named object.
+    #[must_use]
+    pub fn quads(&self, name: &str) -> Option<&[[usize; 4]]> {
+        self.part(name).map(|part| part.quads.as_slice())
+    }
+
+    /// Append the vertices of a woven grid, and the quads joining them.
     ///
-    /// # Panics
-    ///   When a vertex written to the store, cannot be read.
-    pub fn write<W>(mut self, out: &mut BufWriter<W>) -> Result<(), std::io::Error>
+    /// The indices are derived directly from the grid coordinates.
+    /// Normals and texture coordinates are left to the caller,
+    /// see [`Grid::normals`] and [`Grid::uvs`].
+    pub fn push_grid(&mut self, name: String, grid: &Grid) {
+        // wavefront Obj file start at index 1.
+        let offset = self.vertex_buffer.len() + 1;
+        self.vertex_buffer.extend_from_slice(&grid.vertices);
+        let quads = grid.quads().map(|quad| quad.map(|i| i + offset)).collect();
+        self.push_quads(name, quads);
+    }
+
+    /// Append the points of a polyline, such as a fibre, as a line of the named object.
+    ///
+    /// The points join the shared vertex buffer, so lines and faces may be
+    /// mixed in one file. When the OBJ holds normals or texture coordinates
+    /// the new points are given zeroed ones, `l` records do not reference them.
+    pub fn push_polyline(&mut self, name: &str, line: &[Vertex]) {
+        let n = self.vertex_buffer.len();
+        // wavefront Obj file start at index 1.
+        let indices = (n + 1..=n + line.len()).collect();
+        if !self.normals.is_empty() && self.normals.len() == n {
+            self.normals.resize(n + line.len(), Vec3::ZERO);
+        }
+        if !self.uvs.is_empty() && self.uvs.len() == n {
+            self.uvs.resize(n + line.len(), [0_f32; 2]);
+        }
+        self.vertex_buffer.extend_from_slice(line);
+        self.push_line(name, indices);
+    }
+
+    /// Optional pass merging vertices which lie within `tolerance` of each other.
+    ///
+    /// Every object is remapped onto the welded vertex buffer,
+    /// the first vertex in each cluster keeps its normal and texture coordinate.
+    pub fn weld(&mut self, tolerance: f32) {
+        let (welded, remap) = weld(&self.vertex_buffer, tolerance);
+        let remap_index = |i: &mut usize| *i = remap[*i - 1] + 1;
+        for part in &mut self.parts {
+            part.quads.iter_mut().flatten().for_each(remap_index);
+            part.triangles.iter_mut().flatten().for_each(remap_index);
+            part.lines.iter_mut().flatten().for_each(remap_index);
+        }
+
+        // Welding preserves order, so each cluster first appears in turn.
+        let mut kept = Vec::with_capacity(welded.len());
+        for (old, new) in remap.iter().enumerate() {
+            if *new == kept.len() {
+                kept.push(old);
+            }
+        }
+        if self.normals.len() == remap.len() {
+            self.normals = kept.iter().map(|i| self.normals[*i]).collect();
+        }
+        if self.uvs.len() == remap.len() {
+            self.uvs = kept.iter().map(|i| self.uvs[*i]).collect();
+        }
+        self.vertex_buffer = welded;
+    }
+
+    // Attributes and indices must match the vertices.
+    fn validate(&self) -> Result<(), std::io::Error> {
+        let n = self.vertex_buffer.len();
+        check_attribute_lens(
+            "an OBJ file",
+            n,
+            &[("normals", self.normals.len()), ("uvs", self.uvs.len())],
+        )?;
+        if let Some(i) = self
+            .parts
+            .iter()
+            .flat_map(|part| {
+                part.quads
+                    .iter()
+                    .flatten()
+                    .chain(part.triangles.iter().flatten())
+                    .chain(part.lines.iter().flatten())
+            })
+            .find(|i| **i == 0 || **i > n)
+        {
+            return Err(std::io::Error::other(format!(
+                "Cannot write an OBJ file, index {i} is out of range for {n} vertices."
+            )));
+        }
+        Ok(())
+    }
+
+    /// Writes `vertex_buffer`, the per vertex attributes and every object out to file.
+    ///
+    /// # Errors
+    ///   When writing to a buffer fails, or an attribute or index
+    ///   does not match the vertex buffer.
+    pub fn write<W>(self, out: &mut BufWriter<W>) -> Result<(), std::io::Error>
     where
         W: ?Sized + std::io::Write,
     {
-        let mut vertex_buffer = self.vertex_store.drain().collect::<Vec<_>>();
-        vertex_buffer.sort_by(|a, b| {
-            // sort by value
-            a.1.cmp(&b.1)
-        });
+        self.validate()?;
+        if let Some(mtllib) = &self.mtllib {
+            writeln!(out, "mtllib {mtllib}")?;
+        }
 
         // Root vertex list.
-        for (Vertex(Vec3 { x, y, z }), _) in vertex_buffer {
+        for Vertex(Vec3 { x, y, z }) in &self.vertex_buffer {
             writeln!(out, "v {x} {y} {z}")?;
         }
+        for [u, v] in &self.uvs {
+            writeln!(out, "vt {u} {v}")?;
+        }
+        for Vec3 { x, y, z } in &self.normals {
+            writeln!(out, "vn {x} {y} {z}")?;
+        }
+
+        // Attributes share the vertex index.
+        let corner: fn(&usize) -> String = match (self.uvs.is_empty(), self.normals.is_empty()) {
+            (true, true) => |i: &usize| format!("{i}"),
+            (false, true) => |i: &usize| format!("{i}/{i}"),
+            (true, false) => |i: &usize| format!("{i}//{i}"),
+            (false, false) => |i: &usize| format!("{i}/{i}/{i}"),
+        };
 
         // In OBJ files the index runs to 1...=N
-        for (name, quads) in &self.quad_store {
-            writeln!(out, "o {name}")?;
-            // First point of the loop.
-            for quad in quads {
-                writeln!(out, "f {} {} {} {}", quad[0], quad[1], quad[2], quad[3])?;
+        for part in &self.parts {
+            writeln!(out, "o {}", part.name)?;
+            writeln!(out, "g {}", part.name)?;
+            if let Some(material) = &part.material {
+                writeln!(out, "usemtl {material}")?;
+            }
+            let faces = part
+                .quads
+                .iter()
+                .map(<[usize; 4]>::as_slice)
+                .chain(part.triangles.iter().map(<[usize; 3]>::as_slice));
+            for face in faces {
+                let corners = face.iter().map(corner).collect::<Vec<_>>();
+                writeln!(out, "f {}", corners.join(" "))?;
+            }
+            for line in &part.lines {
+                let indices = line.iter().map(ToString::to_string).collect::<Vec<_>>();
+                writeln!(out, "l {}", indices.join(" "))?;
             }
         }
 
         Ok(())
     }
+
+    /// Writes the materials, as a companion `.mtl` file.
+    ///
+    /// # Errors
+    ///   When writing to a buffer fails
+    pub fn write_mtl<W>(&self, out: &mut BufWriter<W>) -> Result<(), std::io::Error>
+    where
+        W: ?Sized + std::io::Write,
+    {
+        for ObjMaterial {
+            name,
+            color: [r, g, b],
+        } in &self.materials
+        {
+            writeln!(out, "newmtl {name}")?;
+            writeln!(out, "Kd {r} {g} {b}")?;
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+}
+
+/// A corner of a face read from an OBJ file, indices are zero based.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ObjIndex {
+    /// Index into [`ObjContents::vertices`].
+    pub vertex: usize,
+    /// Index into [`ObjContents::uvs`].
+    pub uv: Option<usize>,
+    /// Index into [`ObjContents::normals`].
+    pub normal: Option<usize>,
+}
+
+/// A named object read from an OBJ file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ObjObject {
+    /// The name given by the `o` statement, empty before the first.
+    pub name: String,
+    /// The last `usemtl` within the object, if any.
+    pub material: Option<String>,
+    /// Faces of any size.
+    pub faces: Vec<Vec<ObjIndex>>,
+    /// Polylines, as vertex indices.
+    pub lines: Vec<Vec<usize>>,
+}
+
+/// The contents of an OBJ file, see [`ObjContents::read`].
+///
+/// Covers the `v`, `vn`, `vt`, `f`, `l`, `o` and `usemtl` statements.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ObjContents {
+    /// Every `v`, in file order.
+    pub vertices: Vec<Vertex>,
+    /// Every `vn`, in file order.
+    pub normals: Vec<Vec3>,
+    /// Every `vt`, in file order.
+    pub uvs: Vec<[f32; 2]>,
+    /// Objects in file order.
+    pub objects: Vec<ObjObject>,
+}
+
+fn invalid(line: usize, message: &str) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("Cannot read OBJ file, line {line}: {message}."),
+    )
+}
+
+// Resolves a one based, or negative relative, OBJ index.
+fn resolve(token: &str, len: usize) -> Option<usize> {
+    let i = token.parse::<isize>().ok()?;
+    match i {
+        1.. => Some(i.unsigned_abs() - 1),
+        ..0 => len.checked_sub(i.unsigned_abs()),
+        0 => None,
+    }
+    .filter(|i| *i < len)
+}
+
+impl ObjContents {
+    /// Reads the subset of OBJ written by this crate.
+    ///
+    /// Other statements ( such as `g` or `mtllib` ) are skipped.
+    ///
+    /// # Errors
+    ///   When reading fails, or a statement is malformed.
+    pub fn read<R>(input: R) -> Result<Self, std::io::Error>
+    where
+        R: std::io::BufRead,
+    {
+        let mut contents = Self::default();
+        for (i, line) in input.lines().enumerate() {
+            let line = line?;
+            let n = i + 1;
+            let mut words = line.split_ascii_whitespace();
+            let Some(statement) = words.next() else {
+                continue;
+            };
+            let floats = || {
+                words
+                    .clone()
+                    .map(str::parse::<f32>)
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| invalid(n, &e.to_string()))
+            };
+
+            match statement {
+                "v" => match floats()?.as_slice() {
+                    [x, y, z, ..] => contents.vertices.push(Vertex(Vec3::new(*x, *y, *z))),
+                    _ => return Err(invalid(n, "expected x y z")),
+                },
+                "vn" => match floats()?.as_slice() {
+                    [x, y, z] => contents.normals.push(Vec3::new(*x, *y, *z)),
+                    _ => return Err(invalid(n, "expected x y z")),
+                },
+                "vt" => match floats()?.as_slice() {
+                    [u, v, ..] => contents.uvs.push([*u, *v]),
+                    [u] => contents.uvs.push([*u, 0_f32]),
+                    _ => return Err(invalid(n, "expected u v")),
+                },
+                "o" => contents.objects.push(ObjObject {
+                    name: words.collect::<Vec<_>>().join(" "),
+                    ..ObjObject::default()
+                }),
+                "f" => {
+                    let face = words
+                        .map(|corner| contents.corner(corner))
+                        .collect::<Option<Vec<_>>>()
+                        .ok_or_else(|| invalid(n, "face index out of range"))?;
+                    contents.current().faces.push(face);
+                }
+                "l" => {
+                    let n_vertices = contents.vertices.len();
+                    let line = words
+                        .map(|corner| {
+                            resolve(corner.split('/').next().unwrap_or_default(), n_vertices)
+                        })
+                        .collect::<Option<Vec<_>>>()
+                        .ok_or_else(|| invalid(n, "line index out of range"))?;
+                    contents.current().lines.push(line);
+                }
+                "usemtl" => {
+                    contents.current().material = Some(words.collect::<Vec<_>>().join(" "));
+                }
+                _ => {}
+            }
+        }
+        Ok(contents)
+    }
+
+    // Parses `v`, `v/vt`, `v//vn` or `v/vt/vn`.
+    fn corner(&self, corner: &str) -> Option<ObjIndex> {
+        let mut parts = corner.split('/');
+        let vertex = resolve(parts.next()?, self.vertices.len())?;
+        let optional = |part: Option<&str>, len| match part {
+            None | Some("") => Some(None),
+            Some(token) => resolve(token, len).map(Some),
+        };
+        Some(ObjIndex {
+            vertex,
+            uv: optional(parts.next(), self.uvs.len())?,
+            normal: optional(parts.next(), self.normals.len())?,
+        })
+    }
+
+    // Statements before any `o` belong to an unnamed object.
+    fn current(&mut self) -> &mut ObjObject {
+        if self.objects.is_empty() {
+            self.objects.push(ObjObject::default());
+        }
+        let last = self.objects.len() - 1;
+        &mut self.objects[last]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_indices_are_one_based() {
+        let mut grid = Grid::new(2);
+        grid.push_loop(&[Vertex(Vec3::ZERO), Vertex(Vec3::X)]);
+        grid.push_loop(&[Vertex(Vec3::Z), Vertex(Vec3::X + Vec3::Z)]);
+
+        let mut obj = Obj::default();
+        obj.add_vertex(&Vertex(Vec3::Y));
+        obj.push_grid("a".to_string(), &grid);
+
+        assert_eq!(obj.vertex_buffer.len(), 5);
+        assert_eq!(obj.quads("a").unwrap(), &[[2, 3, 5, 4]]);
+    }
+
+    #[test]
+    fn weld_remaps_quads() {
+        // Two grids sharing an edge.
+        let mut a = Grid::new(2);
+        a.push_loop(&[Vertex(Vec3::ZERO), Vertex(Vec3::X)]);
+        a.push_loop(&[Vertex(Vec3::Z), Vertex(Vec3::X + Vec3::Z)]);
+        let mut b = Grid::new(2);
+        b.push_loop(&[Vertex(Vec3::Z), Vertex(Vec3::X + Vec3::Z)]);
+        b.push_loop(&[Vertex(2_f32 * Vec3::Z), Vertex(Vec3::X + 2_f32 * Vec3::Z)]);
+
+        let mut obj = Obj::default();
+        obj.push_grid("a".to_string(), &a);
+        obj.push_grid("b".to_string(), &b);
+        assert_eq!(obj.vertex_buffer.len(), 8);
+
+        obj.weld(1e-6);
+        assert_eq!(obj.vertex_buffer.len(), 6);
+        assert_eq!(obj.quads("a").unwrap(), &[[1, 2, 4, 3]]);
+        assert_eq!(obj.quads("b").unwrap(), &[[3, 4, 6, 5]]);
+    }
+
+    #[test]
+    fn objects_keep_insertion_order() {
+        let build = || {
+            let mut obj = Obj::default();
+            for v in [Vec3::ZERO, Vec3::X, Vec3::ONE, Vec3::Y] {
+                obj.add_vertex(&Vertex(v));
+            }
+            for name in ["z", "a", "m", "b"] {
+                obj.push_quads(name.to_string(), vec![[1, 2, 3, 4]]);
+            }
+            // Replacing keeps the original position.
+            obj.push_quads("a".to_string(), vec![[4, 3, 2, 1]]);
+            let mut out = BufWriter::new(vec![]);
+            obj.write(&mut out).unwrap();
+            String::from_utf8(out.into_inner().unwrap()).unwrap()
+        };
+
+        let text = build();
+        let names = text
+            .lines()
+            .filter_map(|line| line.strip_prefix("o "))
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["z", "a", "m", "b"]);
+        assert!(text.contains("o a\ng a\nf 4 3 2 1\n"));
+        assert_eq!(text, build());
+    }
+
+    #[test]
+    fn read_back_quads() {
+        let mut grid = Grid::new(3);
+        grid.push_loop(&[Vertex(Vec3::ZERO), Vertex(Vec3::X), Vertex(Vec3::Y)]);
+        grid.push_loop(&[Vertex(Vec3::Z), Vertex(Vec3::ONE), Vertex(-Vec3::X)]);
+        let mut obj = Obj::default();
+        obj.push_grid("sheet".to_string(), &grid);
+        let mut out = BufWriter::new(Vec::new());
+        obj.write(&mut out).unwrap();
+
+        let contents = ObjContents::read(out.into_inner().unwrap().as_slice()).unwrap();
+        assert_eq!(contents.vertices, grid.vertices);
+        assert_eq!(contents.objects.len(), 1);
+        assert_eq!(contents.objects[0].name, "sheet");
+        let faces = contents.objects[0]
+            .faces
+            .iter()
+            .map(|face| face.iter().map(|c| c.vertex).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(faces, grid.quads().map(Vec::from).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn write_attributes_and_materials() {
+        let mut grid = Grid::new(2);
+        grid.push_loop(&[Vertex(Vec3::ZERO), Vertex(Vec3::X)]);
+        grid.push_loop(&[Vertex(Vec3::Z), Vertex(Vec3::X + Vec3::Z)]);
+        let mut obj = Obj {
+            mtllib: Some("shells.mtl".to_string()),
+            materials: vec![ObjMaterial {
+                name: "outer".to_string(),
+                color: [1_f32, 0.5, 0_f32],
+            }],
+            ..Obj::default()
+        };
+        obj.push_grid("outer".to_string(), &grid);
+        obj.normals.extend(grid.normals());
+        obj.uvs.extend(grid.uvs());
+        obj.part_mut("outer").material = Some("outer".to_string());
+        obj.push_triangles("cap".to_string(), vec![[1, 2, 3]]);
+        obj.push_line("fibres", vec![1, 3, 4]);
+
+        let mut mtl = BufWriter::new(vec![]);
+        obj.write_mtl(&mut mtl).unwrap();
+        let mtl = String::from_utf8(mtl.into_inner().unwrap()).unwrap();
+        assert_eq!(mtl, "newmtl outer\nKd 1 0.5 0\n\n");
+
+        let mut out = BufWriter::new(vec![]);
+        obj.write(&mut out).unwrap();
+        let text = String::from_utf8(out.into_inner().unwrap()).unwrap();
+        assert!(text.starts_with("mtllib shells.mtl\n"));
+        assert!(text.contains("o outer\ng outer\nusemtl outer\nf 1/1/1 2/2/2 4/4/4 3/3/3\n"));
+
+        let contents = ObjContents::read(text.as_bytes()).unwrap();
+        assert_eq!(contents.normals.len(), 4);
+        assert!(glam::Vec2::from(contents.uvs[3]).abs_diff_eq(glam::Vec2::ONE, 1e-6));
+        assert_eq!(contents.objects[0].material.as_deref(), Some("outer"));
+        assert_eq!(contents.objects[1].faces[0].len(), 3);
+        assert_eq!(contents.objects[2].lines, vec![vec![0, 2, 3]]);
+    }
+
+    #[test]
+    fn surfaces_and_fibres_share_vertices() {
+        let mut grid = Grid::new(2);
+        grid.push_loop(&[Vertex(Vec3::ZERO), Vertex(Vec3::X)]);
+        grid.push_loop(&[Vertex(Vec3::Z), Vertex(Vec3::X + Vec3::Z)]);
+        let mut obj = Obj::default();
+        obj.push_grid("shell".to_string(), &grid);
+        obj.normals.extend(grid.normals());
+        // A fibre traced over the shell, and a free standing base curve.
+        obj.push_line("fibre", vec![1, 3]);
+        obj.push_polyline("base", &[Vertex(Vec3::Y), Vertex(Vec3::ONE)]);
+        assert_eq!(obj.normals.len(), 6);
+
+        let mut out = BufWriter::new(vec![]);
+        obj.write(&mut out).unwrap();
+        let contents = ObjContents::read(out.into_inner().unwrap().as_slice()).unwrap();
+        let names = contents
+            .objects
+            .iter()
+            .map(|o| o.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["shell", "fibre", "base"]);
+        assert_eq!(contents.objects[0].faces.len(), 1);
+        assert_eq!(contents.objects[1].lines, vec![vec![0, 2]]);
+        assert_eq!(contents.objects[2].lines, vec![vec![4, 5]]);
+        assert_eq!(contents.vertices[5], Vertex(Vec3::ONE));
+    }
+
+    #[test]
+    fn write_rejects_partial_attributes() {
+        let mut obj = Obj::default();
+        obj.add_vertex(&Vertex(Vec3::ZERO));
+        obj.add_vertex(&Vertex(Vec3::X));
+        obj.normals.push(Vec3::Z);
+        assert!(obj.write(&mut BufWriter::new(vec![])).is_err());
+
+        let mut obj = Obj::default();
+        obj.add_vertex(&Vertex(Vec3::ZERO));
+        obj.push_line("fibre", vec![1, 2]);
+        assert!(obj.write(&mut BufWriter::new(vec![])).is_err());
+    }
+
+    #[test]
+    fn read_lines_and_attributes() {
+        let text = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0.5 1\nvn 0 0 1\n\
+                    o tri\nf 1/1/1 2//1 -1\no fibre_0\nl 1 2 3\n";
+        let contents = ObjContents::read(text.as_bytes()).unwrap();
+        assert_eq!(contents.uvs, vec![[0.5, 1.0]]);
+        assert_eq!(
+            contents.objects[0].faces[0],
+            vec![
+                ObjIndex {
+                    vertex: 0,
+                    uv: Some(0),
+                    normal: Some(0)
+                },
+                ObjIndex {
+                    vertex: 1,
+                    uv: None,
+                    normal: Some(0)
+                },
+                ObjIndex {
+                    vertex: 2,
+                    uv: None,
+                    normal: None
+                },
+            ]
+        );
+        assert_eq!(contents.objects[1].lines, vec![vec![0, 1, 2]]);
+
+        assert!(ObjContents::read(&b"v 0 0 0\nf 1 2 3\n"[..]).is_err());
+    }
+
+    #[test]
+    fn reads_checked_in_fibres() {
+        let lines = include_str!("../../points2Obj_lines/a.obj");
+        let contents = ObjContents::read(lines.as_bytes()).unwrap();
+        assert!(!contents.objects.is_empty());
+        // Every fibre is a single polyline, through its own vertices.
+        let mut next = 0;
+        for object in &contents.objects {
+            assert_eq!(object.lines.len(), 1);
+            let line = &object.lines[0];
+            assert_eq!(line, &(next..next + line.len()).collect::<Vec<_>>());
+            next += line.len();
+        }
+        let n_points = next;
+        assert_eq!(n_points, contents.vertices.len());
+    }
 }
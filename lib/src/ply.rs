@@ -0,0 +1,693 @@
+use crate::Vertex;
+use crate::check_attribute_lens;
+use crate::mesh::Grid;
+use std::io::BufWriter;
+use std::io::Error;
+use std::io::Write;
+
+use glam::Vec3;
+
+/// Encoding of the body of a PLY file, the header is always ASCII.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PlyFormat {
+    /// Human readable, one element per line.
+    #[default]
+    Ascii,
+    /// Compact `binary_little_endian 1.0`.
+    BinaryLittleEndian,
+}
+
+impl PlyFormat {
+    const fn header(self) -> &'static str {
+        match self {
+            Self::Ascii => "ascii",
+            Self::BinaryLittleEndian => "binary_little_endian",
+        }
+    }
+}
+
+/// Hold state information related to the storage of
+/// vertices, faces and edges in a PLY file.
+///
+/// Unlike OBJ files, PLY indices start at 0.
+/// Normals and colours are optional, when present there must be one per vertex.
+#[derive(Debug, Default, PartialEq)]
+pub struct Ply {
+    /// All points that appear in the file, in index order.
+    pub vertex_buffer: Vec<Vertex>,
+    /// Per vertex normals, or empty.
+    pub normals: Vec<Vec3>,
+    /// Per vertex RGB colours, or empty.
+    pub colors: Vec<[u8; 3]>,
+    /// Indices of every face, concatenated, see `face_sizes`.
+    pub face_store: Vec<u32>,
+    /// The number of indices in each face.
+    pub face_sizes: Vec<u8>,
+    /// Pairs of vertex indices, such as the segments of a fibre.
+    pub edge_store: Vec<[u32; 2]>,
+}
+
+// Indices are written as a PLY `int`.
+fn to_index(i: usize) -> Result<u32, Error> {
+    i32::try_from(i)
+        .ok()
+        .and_then(|i| u32::try_from(i).ok())
+        .ok_or_else(|| Error::other(format!("Cannot index vertex {i} in a PLY file.")))
+}
+
+// The header up to the vertex positions, shared with streamed point clouds.
+fn write_vertex_header<W>(
+    format: PlyFormat,
+    n_vertices: usize,
+    out: &mut BufWriter<W>,
+) -> Result<(), Error>
+where
+    W: ?Sized + std::io::Write,
+{
+    writeln!(out, "ply")?;
+    writeln!(out, "format {} 1.0", format.header())?;
+    writeln!(out, "element vertex {n_vertices}")?;
+    writeln!(out, "property float x")?;
+    writeln!(out, "property float y")?;
+    writeln!(out, "property float z")?;
+    Ok(())
+}
+
+/// Streams a point cloud, the points are written as they are produced.
+///
+/// Unlike [`Ply::write`] the points are never held in memory, the output
+/// matches that of a [`Ply`] holding only the vertices.
+///
+/// # Errors
+///   When writing to a buffer fails.
+pub fn write_point_cloud<I, W>(
+    points: I,
+    format: PlyFormat,
+    out: &mut BufWriter<W>,
+) -> Result<(), Error>
+where
+    I: ExactSizeIterator<Item = Vertex>,
+    W: ?Sized + std::io::Write,
+{
+    write_vertex_header(format, points.len(), out)?;
+    writeln!(out, "end_header")?;
+    for Vertex(v) in points {
+        match format {
+            PlyFormat::Ascii => writeln!(out, "{} {} {}", v.x, v.y, v.z)?,
+            PlyFormat::BinaryLittleEndian => {
+                for c in v.to_array() {
+                    out.write_all(&c.to_le_bytes())?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+impl Ply {
+    /// Append a face, the indices refer to `vertex_buffer`.
+    ///
+    /// # Errors
+    ///   When the face has more than 255 vertices.
+    pub fn push_face(&mut self, face: &[u32]) -> Result<(), Error> {
+        let size = u8::try_from(face.len()).map_err(|_| {
+            Error::other(format!(
+                "Cannot add a PLY face of {} vertices, at most 255 are allowed.",
+                face.len()
+            ))
+        })?;
+        self.face_sizes.push(size);
+        self.face_store.extend_from_slice(face);
+        Ok(())
+    }
+
+    /// Iterates over the faces, as slices of `face_store`.
+    pub fn faces(&self) -> impl Iterator<Item = &[u32]> {
+        self.face_sizes.iter().scan(0, |start, size| {
+            let face = &self.face_store[*start..*start + usize::from(*size)];
+            *start += usize::from(*size);
+            Some(face)
+        })
+    }
+
+    /// Append the vertices of a woven grid, and the quads joining them.
+    ///
+    /// # Errors
+    ///   When the vertices cannot be indexed by a PLY `int`.
+    pub fn push_grid(&mut self, grid: &Grid) -> Result<(), Error> {
+        let offset = self.vertex_buffer.len();
+        to_index(offset + grid.vertices.len())?;
+        self.vertex_buffer.extend_from_slice(&grid.vertices);
+        for quad in grid.quads() {
+            // Checked above, every index fits.
+            #[allow(clippy::cast_possible_truncation)]
+            let quad = quad.map(|i| (i + offset) as u32);
+            self.push_face(&quad)?;
+        }
+        Ok(())
+    }
+
+    /// Append a polyline, such as a fibre, as a chain of edges.
+    ///
+    /// # Errors
+    ///   When the vertices cannot be indexed by a PLY `int`.
+    pub fn push_polyline(&mut self, line: &[Vertex]) -> Result<(), Error> {
+        let offset = to_index(self.vertex_buffer.len())?;
+        to_index(self.vertex_buffer.len() + line.len())?;
+        self.vertex_buffer.extend_from_slice(line);
+        // Checked above, every index fits.
+        #[allow(clippy::cast_possible_truncation)]
+        let n = line.len() as u32;
+        self.edge_store
+            .extend((1..n).map(|i| [offset + i - 1, offset + i]));
+        Ok(())
+    }
+
+    // Attributes and indices must agree with the vertex buffer.
+    fn validate(&self) -> Result<(), Error> {
+        let n = self.vertex_buffer.len();
+        to_index(n)?;
+        check_attribute_lens(
+            "a PLY file",
+            n,
+            &[
+                ("normals", self.normals.len()),
+                ("colors", self.colors.len()),
+            ],
+        )?;
+        if let Some(i) = self
+            .face_store
+            .iter()
+            .chain(self.edge_store.iter().flatten())
+            .find(|i| **i as usize >= n)
+        {
+            return Err(Error::other(format!(
+                "Cannot write a PLY file, index {i} is out of range for {n} vertices."
+            )));
+        }
+        Ok(())
+    }
+
+    fn write_header<W>(&self, format: PlyFormat, out: &mut BufWriter<W>) -> Result<(), Error>
+    where
+        W: ?Sized + std::io::Write,
+    {
+        write_vertex_header(format, self.vertex_buffer.len(), out)?;
+        if !self.normals.is_empty() {
+            writeln!(out, "property float nx")?;
+            writeln!(out, "property float ny")?;
+            writeln!(out, "property float nz")?;
+        }
+        if !self.colors.is_empty() {
+            writeln!(out, "property uchar red")?;
+            writeln!(out, "property uchar green")?;
+            writeln!(out, "property uchar blue")?;
+        }
+        // Empty elements are left out, keeping point clouds minimal.
+        if !self.face_sizes.is_empty() {
+            writeln!(out, "element face {}", self.face_sizes.len())?;
+            writeln!(out, "property list uchar int vertex_indices")?;
+        }
+        if !self.edge_store.is_empty() {
+            writeln!(out, "element edge {}", self.edge_store.len())?;
+            writeln!(out, "property int vertex1")?;
+            writeln!(out, "property int vertex2")?;
+        }
+        writeln!(out, "end_header")?;
+        Ok(())
+    }
+
+    fn write_ascii<W>(&self, out: &mut BufWriter<W>) -> Result<(), Error>
+    where
+        W: ?Sized + std::io::Write,
+    {
+        for (i, Vertex(Vec3 { x, y, z })) in self.vertex_buffer.iter().enumerate() {
+            write!(out, "{x} {y} {z}")?;
+            if let Some(Vec3 { x, y, z }) = self.normals.get(i) {
+                write!(out, " {x} {y} {z}")?;
+            }
+            if let Some([r, g, b]) = self.colors.get(i) {
+                write!(out, " {r} {g} {b}")?;
+            }
+            writeln!(out)?;
+        }
+        for face in self.faces() {
+            write!(out, "{}", face.len())?;
+            for i in face {
+                write!(out, " {i}")?;
+            }
+            writeln!(out)?;
+        }
+        for [a, b] in &self.edge_store {
+            writeln!(out, "{a} {b}")?;
+        }
+        Ok(())
+    }
+
+    fn write_binary<W>(&self, out: &mut BufWriter<W>) -> Result<(), Error>
+    where
+        W: ?Sized + std::io::Write,
+    {
+        for (i, Vertex(v)) in self.vertex_buffer.iter().enumerate() {
+            for c in v.to_array() {
+                out.write_all(&c.to_le_bytes())?;
+            }
+            if let Some(n) = self.normals.get(i) {
+                for c in n.to_array() {
+                    out.write_all(&c.to_le_bytes())?;
+                }
+            }
+            if let Some(rgb) = self.colors.get(i) {
+                out.write_all(rgb)?;
+            }
+        }
+        // Validated, every index fits into an int.
+        for face in self.faces() {
+            #[allow(clippy::cast_possible_truncation)]
+            out.write_all(&[face.len() as u8])?;
+            for i in face {
+                out.write_all(&i.to_le_bytes())?;
+            }
+        }
+        for edge in &self.edge_store {
+            for i in edge {
+                out.write_all(&i.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the header, vertices, faces and edges out to file.
+    ///
+    /// # Errors
+    ///   When writing to a buffer fails, or when the normals, colours or
+    ///   indices do not match `vertex_buffer`.
+    pub fn write<W>(&self, format: PlyFormat, out: &mut BufWriter<W>) -> Result<(), Error>
+    where
+        W: ?Sized + std::io::Write,
+    {
+        self.validate()?;
+        self.write_header(format, out)?;
+        match format {
+            PlyFormat::Ascii => self.write_ascii(out),
+            PlyFormat::BinaryLittleEndian => self.write_binary(out),
+        }
+    }
+}
+
+// The scalar types of a PLY property.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> Result<Self, Error> {
+        Ok(match name {
+            "char" | "int8" => Self::I8,
+            "uchar" | "uint8" => Self::U8,
+            "short" | "int16" => Self::I16,
+            "ushort" | "uint16" => Self::U16,
+            "int" | "int32" => Self::I32,
+            "uint" | "uint32" => Self::U32,
+            "float" | "float32" => Self::F32,
+            "double" | "float64" => Self::F64,
+            _ => return Err(invalid(&format!("unknown property type {name}"))),
+        })
+    }
+
+    const fn size(self) -> usize {
+        match self {
+            Self::I8 | Self::U8 => 1,
+            Self::I16 | Self::U16 => 2,
+            Self::I32 | Self::U32 | Self::F32 => 4,
+            Self::F64 => 8,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Property {
+    name: String,
+    // The count type of a list property.
+    list: Option<Scalar>,
+    scalar: Scalar,
+}
+
+#[derive(Debug)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+fn invalid(message: &str) -> Error {
+    Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("Cannot read PLY file, {message}."),
+    )
+}
+
+// Reads values, of any scalar type, from the body of the file.
+enum Body<'a> {
+    Ascii(core::str::SplitAsciiWhitespace<'a>),
+    Binary(&'a [u8]),
+}
+
+impl Body<'_> {
+    fn next(&mut self, scalar: Scalar) -> Result<f64, Error> {
+        match self {
+            Self::Ascii(tokens) => {
+                let token = tokens
+                    .next()
+                    .ok_or_else(|| invalid("the body ends early"))?;
+                // Parsed at their own precision, avoiding a double rounding.
+                let value = if scalar == Scalar::F32 {
+                    token.parse::<f32>().map(f64::from)
+                } else {
+                    token.parse::<f64>()
+                };
+                value.map_err(|e| invalid(&format!("{token}: {e}")))
+            }
+            Self::Binary(bytes) => {
+                if bytes.len() < scalar.size() {
+                    return Err(invalid("the body ends early"));
+                }
+                let (value, rest) = bytes.split_at(scalar.size());
+                *bytes = rest;
+                // Sizes match, checked above.
+                let value = match scalar {
+                    Scalar::I8 => f64::from(i8::from_le_bytes([value[0]])),
+                    Scalar::U8 => f64::from(value[0]),
+                    Scalar::I16 => f64::from(i16::from_le_bytes([value[0], value[1]])),
+                    Scalar::U16 => f64::from(u16::from_le_bytes([value[0], value[1]])),
+                    Scalar::I32 => {
+                        f64::from(i32::from_le_bytes(value.try_into().unwrap_or_default()))
+                    }
+                    Scalar::U32 => {
+                        f64::from(u32::from_le_bytes(value.try_into().unwrap_or_default()))
+                    }
+                    Scalar::F32 => {
+                        f64::from(f32::from_le_bytes(value.try_into().unwrap_or_default()))
+                    }
+                    Scalar::F64 => f64::from_le_bytes(value.try_into().unwrap_or_default()),
+                };
+                Ok(value)
+            }
+        }
+    }
+}
+
+// Splits the file after `end_header`, returning the header text.
+fn split_header(bytes: &[u8]) -> Result<(&str, &[u8]), Error> {
+    let marker = b"end_header";
+    let end = bytes
+        .windows(marker.len())
+        .position(|w| w == marker)
+        .ok_or_else(|| invalid("missing end_header"))?;
+    // The header line ends with \n, or \r\n.
+    let mut body = end + marker.len();
+    if bytes.get(body) == Some(&b'\r') {
+        body += 1;
+    }
+    if bytes.get(body) == Some(&b'\n') {
+        body += 1;
+    }
+    let header = core::str::from_utf8(&bytes[..end])
+        .map_err(|e| invalid(&format!("the header is not text: {e}")))?;
+    Ok((header, &bytes[body..]))
+}
+
+fn parse_header(header: &str) -> Result<(PlyFormat, Vec<Element>), Error> {
+    let mut lines = header.lines().map(str::trim);
+    if lines.next() != Some("ply") {
+        return Err(invalid("missing the ply magic number"));
+    }
+
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    for line in lines {
+        let words = line.split_ascii_whitespace().collect::<Vec<_>>();
+        match words.as_slice() {
+            [] | ["comment" | "obj_info", ..] => {}
+            ["format", "ascii", "1.0"] => format = Some(PlyFormat::Ascii),
+            ["format", "binary_little_endian", "1.0"] => {
+                format = Some(PlyFormat::BinaryLittleEndian);
+            }
+            ["element", name, count] => elements.push(Element {
+                name: (*name).to_string(),
+                count: count
+                    .parse()
+                    .map_err(|e| invalid(&format!("element {name} count {count}: {e}")))?,
+                properties: Vec::new(),
+            }),
+            ["property", rest @ ..] => {
+                let property = match rest {
+                    ["list", count, scalar, name] => Property {
+                        name: (*name).to_string(),
+                        list: Some(Scalar::parse(count)?),
+                        scalar: Scalar::parse(scalar)?,
+                    },
+                    [scalar, name] => Property {
+                        name: (*name).to_string(),
+                        list: None,
+                        scalar: Scalar::parse(scalar)?,
+                    },
+                    _ => return Err(invalid(&format!("malformed {line}"))),
+                };
+                elements
+                    .last_mut()
+                    .ok_or_else(|| invalid(&format!("{line} precedes any element")))?
+                    .properties
+                    .push(property);
+            }
+            _ => return Err(invalid(&format!("unsupported header line {line}"))),
+        }
+    }
+
+    let format = format.ok_or_else(|| invalid("missing format"))?;
+    Ok((format, elements))
+}
+
+// The values of one element, indexed as `element.properties`.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn read_row(body: &mut Body, element: &Element) -> Result<Vec<Vec<f64>>, Error> {
+    element
+        .properties
+        .iter()
+        .map(|property| match property.list {
+            None => Ok(vec![body.next(property.scalar)?]),
+            Some(count) => {
+                let n = body.next(count)?;
+                (0..n as usize)
+                    .map(|_| body.next(property.scalar))
+                    .collect()
+            }
+        })
+        .collect()
+}
+
+impl Ply {
+    /// Reads a PLY file, such as those written by [`Ply::write`].
+    ///
+    /// Both ASCII and `binary_little_endian` files are supported.
+    /// Unknown elements and properties are skipped.
+    ///
+    /// # Errors
+    ///   When reading fails, or the file is malformed.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn read<R>(mut input: R) -> Result<Self, Error>
+    where
+        R: std::io::Read,
+    {
+        let mut bytes = Vec::new();
+        input.read_to_end(&mut bytes)?;
+        let (header, body) = split_header(&bytes)?;
+        let (format, elements) = parse_header(header)?;
+        let mut body = match format {
+            PlyFormat::Ascii => Body::Ascii(
+                core::str::from_utf8(body)
+                    .map_err(|e| invalid(&format!("the body is not text: {e}")))?
+                    .split_ascii_whitespace(),
+            ),
+            PlyFormat::BinaryLittleEndian => Body::Binary(body),
+        };
+
+        let mut ply = Self::default();
+        for element in &elements {
+            let position = |name: &str| element.properties.iter().position(|p| p.name == name);
+            let xyz = ["x", "y", "z"].map(position);
+            let normal = ["nx", "ny", "nz"].map(position);
+            let color = ["red", "green", "blue"].map(position);
+            let indices = position("vertex_indices").or_else(|| position("vertex_index"));
+            let edge = ["vertex1", "vertex2"].map(position);
+
+            for _ in 0..element.count {
+                let row = read_row(&mut body, element)?;
+                let scalar = |i: Option<usize>| i.map_or(0_f64, |i| row[i][0]);
+                match element.name.as_str() {
+                    "vertex" => {
+                        let [x, y, z] = xyz.map(|i| scalar(i) as f32);
+                        ply.vertex_buffer.push(Vertex(Vec3::new(x, y, z)));
+                        if normal.iter().all(Option::is_some) {
+                            ply.normals
+                                .push(Vec3::from(normal.map(|i| scalar(i) as f32)));
+                        }
+                        if color.iter().all(Option::is_some) {
+                            ply.colors
+                                .push(color.map(|i| scalar(i).clamp(0_f64, 255_f64) as u8));
+                        }
+                    }
+                    "face" => {
+                        let Some(i) = indices else { continue };
+                        let face = row[i].iter().map(|v| *v as u32).collect::<Vec<_>>();
+                        ply.push_face(&face)
+                            .map_err(|_| invalid(&format!("a face of {} vertices", face.len())))?;
+                    }
+                    "edge" if edge.iter().all(Option::is_some) => {
+                        ply.edge_store.push(edge.map(|i| scalar(i) as u32));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        ply.validate()?;
+        Ok(ply)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square() -> Ply {
+        let mut grid = Grid::new(2);
+        grid.push_loop(&[Vertex(Vec3::ZERO), Vertex(Vec3::X)]);
+        grid.push_loop(&[Vertex(Vec3::Z), Vertex(Vec3::X + Vec3::Z)]);
+        let mut ply = Ply::default();
+        ply.push_grid(&grid).unwrap();
+        ply
+    }
+
+    fn written(ply: &Ply, format: PlyFormat) -> Vec<u8> {
+        let mut out = BufWriter::new(Vec::new());
+        ply.write(format, &mut out).unwrap();
+        out.into_inner().unwrap()
+    }
+
+    #[test]
+    fn ascii_faces_and_edges() {
+        let mut ply = square();
+        ply.push_polyline(&[Vertex(Vec3::Y), Vertex(Vec3::ONE), Vertex(Vec3::X)])
+            .unwrap();
+        ply.colors = vec![[255, 0, 0]; 7];
+
+        let text = String::from_utf8(written(&ply, PlyFormat::Ascii)).unwrap();
+        let (header, body) = text.split_once("end_header\n").unwrap();
+        assert!(header.contains("element vertex 7\n"));
+        assert!(header.contains("property uchar red\n"));
+        assert!(!header.contains("nx"));
+        assert!(header.contains("element face 1\n"));
+        assert!(header.contains("element edge 2\n"));
+
+        let lines = body.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "0 0 0 255 0 0");
+        assert_eq!(lines[7], "4 0 1 3 2");
+        assert_eq!(&lines[8..], &["4 5", "5 6"]);
+    }
+
+    #[test]
+    fn binary_body_size() {
+        let mut ply = square();
+        ply.normals = vec![Vec3::Y; 4];
+
+        let bytes = written(&ply, PlyFormat::BinaryLittleEndian);
+        let header_end = b"end_header\n";
+        let start = bytes
+            .windows(header_end.len())
+            .position(|w| w == header_end)
+            .unwrap()
+            + header_end.len();
+        assert!(bytes.starts_with(b"ply\nformat binary_little_endian 1.0\n"));
+        // 4 vertices of 6 floats, one quad of a count and 4 ints.
+        assert_eq!(bytes.len() - start, 4 * 6 * 4 + 1 + 4 * 4);
+        // The second vertex starts with x = 1.
+        assert_eq!(&bytes[start + 24..start + 28], &1_f32.to_le_bytes());
+    }
+
+    #[test]
+    fn mismatched_normals_are_rejected() {
+        let mut ply = square();
+        ply.normals = vec![Vec3::Y; 3];
+        let mut out = BufWriter::new(Vec::new());
+        assert!(ply.write(PlyFormat::Ascii, &mut out).is_err());
+    }
+
+    #[test]
+    fn streamed_point_clouds_match() {
+        let ply = Ply {
+            vertex_buffer: vec![Vertex(Vec3::ZERO), Vertex(Vec3::new(0.5, -1.0, 2.0))],
+            ..Ply::default()
+        };
+        let points = || ply.vertex_buffer.clone().into_iter();
+        for format in [PlyFormat::Ascii, PlyFormat::BinaryLittleEndian] {
+            let mut out = BufWriter::new(Vec::new());
+            write_point_cloud(points(), format, &mut out).unwrap();
+            assert_eq!(out.into_inner().unwrap(), written(&ply, format));
+        }
+
+        let mut out = BufWriter::new(Vec::new());
+        crate::generate_ply(points(), &mut out).unwrap();
+        assert_eq!(out.into_inner().unwrap(), written(&ply, PlyFormat::Ascii));
+    }
+
+    #[test]
+    fn oversized_faces_are_rejected() {
+        let mut ply = square();
+        assert!(ply.push_face(&[0; 256]).is_err());
+        assert_eq!(ply.faces().count(), 1);
+        ply.push_face(&[0; 255]).unwrap();
+        assert_eq!(ply.faces().last().unwrap().len(), 255);
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut ply = square();
+        ply.push_polyline(&[Vertex(Vec3::Y), Vertex(Vec3::ONE)])
+            .unwrap();
+        ply.normals = vec![Vec3::new(0.25, -1.5, 1e-7); 6];
+        ply.colors = vec![[1, 2, 250]; 6];
+
+        for format in [PlyFormat::Ascii, PlyFormat::BinaryLittleEndian] {
+            let read = Ply::read(written(&ply, format).as_slice()).unwrap();
+            assert_eq!(read, ply);
+        }
+    }
+
+    #[test]
+    fn reads_golden_file() {
+        let golden = include_str!("../../point2ply/golden.ply");
+        let ply = Ply::read(golden.as_bytes()).unwrap();
+        assert_eq!(ply.vertex_buffer.len(), 20);
+        assert!(ply.normals.is_empty() && ply.face_sizes.is_empty());
+        assert!(
+            Vec3::from(ply.vertex_buffer[0])
+                .abs_diff_eq(Vec3::new(0.224_163_45, 1.452_394_3, 8.013_912), 1e-6)
+        );
+
+        assert!(
+            Ply::read(
+                &b"ply\nformat ascii 1.0\nelement vertex 2\nproperty float x\nend_header\n1\n"[..]
+            )
+            .is_err()
+        );
+    }
+}
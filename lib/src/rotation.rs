@@ -0,0 +1,88 @@
+use glam::Mat4;
+
+/// A coordinate plane of E4, in which S³ can be rotated.
+///
+/// Coordinates are ordered (X1, X2, X3, X4) as in [`crate::project`],
+/// X4 is the axis of the stereographic projection.
+/// So rotations involving W carry points through infinity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Plane {
+    /// Rotates X1 towards X2.
+    XY,
+    /// Rotates X1 towards X3.
+    XZ,
+    /// Rotates X1 towards X4.
+    XW,
+    /// Rotates X2 towards X3.
+    YZ,
+    /// Rotates X2 towards X4.
+    YW,
+    /// Rotates X3 towards X4.
+    ZW,
+}
+
+impl Plane {
+    /// All six planes, in the order used by [`rotation_from_angles`].
+    pub const ALL: [Self; 6] = [Self::XY, Self::XZ, Self::XW, Self::YZ, Self::YW, Self::ZW];
+
+    // The pair of axes spanning the plane.
+    const fn axes(self) -> (usize, usize) {
+        match self {
+            Self::XY => (0, 1),
+            Self::XZ => (0, 2),
+            Self::XW => (0, 3),
+            Self::YZ => (1, 2),
+            Self::YW => (1, 3),
+            Self::ZW => (2, 3),
+        }
+    }
+}
+
+/// A rotation by `angle` ( radians ) in a single plane.
+#[must_use]
+pub fn plane_rotation(plane: Plane, angle: f32) -> Mat4 {
+    let (a, b) = plane.axes();
+    let (s, c) = angle.sin_cos();
+
+    // Column major, col(j)[i] is row i of column j.
+    let mut m = Mat4::IDENTITY;
+    m.col_mut(a)[a] = c;
+    m.col_mut(a)[b] = s;
+    m.col_mut(b)[a] = -s;
+    m.col_mut(b)[b] = c;
+    m
+}
+
+/// Composes one rotation per plane, angles are given in [`Plane::ALL`] order.
+///
+/// Storing angles, rather than accumulating matrices, avoids drift.
+#[must_use]
+pub fn rotation_from_angles(angles: &[f32; 6]) -> Mat4 {
+    Plane::ALL
+        .iter()
+        .zip(angles)
+        .fold(Mat4::IDENTITY, |m, (plane, angle)| {
+            plane_rotation(*plane, *angle) * m
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec4;
+
+    use super::*;
+
+    #[test]
+    fn quarter_turn_moves_x_to_w() {
+        let m = plane_rotation(Plane::XW, core::f32::consts::FRAC_PI_2);
+        assert!((m * Vec4::X).abs_diff_eq(Vec4::W, 1e-6));
+        assert!((m * Vec4::W).abs_diff_eq(-Vec4::X, 1e-6));
+    }
+
+    #[test]
+    fn composition_is_orthogonal() {
+        let m = rotation_from_angles(&[0.1, -0.2, 0.3, 0.4, -0.5, 0.6]);
+        assert!((m * m.transpose()).abs_diff_eq(Mat4::IDENTITY, 1e-6));
+        assert_eq!(rotation_from_angles(&[0_f32; 6]), Mat4::IDENTITY);
+    }
+}
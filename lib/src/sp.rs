@@ -8,7 +8,7 @@ use glam::Vec3;
 ///
 /// I could use (f32, f32) or `glam::Vec` but I
 /// explicit field labels lat and lon.
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq)]
 pub struct SurfacePoint {
     /// latitude ( radians )
     pub lat: f32,
@@ -68,11 +68,16 @@ impl SurfacePoint {
         }
     }
 
-    fn extract_surface_point(direction: Vec3) -> Self {
-        let Vec3 { x, y, z } = direction;
+    /// The inverse of [`SurfacePoint::to_cartesian`].
+    ///
+    /// The direction need not be normalized.
+    /// The longitude is returned in the range 0..TAU.
+    #[must_use]
+    pub fn from_cartesian(direction: Vec3) -> Self {
+        let Vec3 { x, y, z } = direction.normalize();
         // hypotenu is 1.
-        let lat = f32::asin(y);
-        let lon = f32::atan2(-z, x);
+        let lat = f32::asin(y.clamp(-1_f32, 1_f32));
+        let lon = f32::atan2(x, -z).rem_euclid(core::f32::consts::TAU);
         Self { lat, lon }
     }
 }
@@ -180,4 +185,21 @@ mod test {
             );
         }
     }
+
+    #[test]
+    fn round_trip() {
+        for lat in [-80_f32, -45_f32, 0_f32, 10_f32, 60_f32] {
+            for lon in [0_f32, 45_f32, 135_f32, 200_f32, 270_f32, 359_f32] {
+                let sp = SurfacePoint {
+                    lat: lat.to_radians(),
+                    lon: lon.to_radians(),
+                };
+                let output = SurfacePoint::from_cartesian(sp.to_cartesian(2_f32));
+                assert!(
+                    (output.lat - sp.lat).abs() < 1e-5 && (output.lon - sp.lon).abs() < 1e-5,
+                    "Failed: sp {sp:#?} -> {output:#?}"
+                );
+            }
+        }
+    }
 }
@@ -0,0 +1,295 @@
+use crate::Vertex;
+use crate::mesh::Grid;
+use crate::weld::weld;
+use std::collections::HashMap;
+use std::io::BufWriter;
+use std::io::Error;
+use std::io::ErrorKind;
+use std::io::Write;
+
+use glam::Vec3;
+
+/// Encoding of an STL file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StlFormat {
+    /// Human readable `solid ... endsolid`.
+    Ascii,
+    /// Compact, 50 bytes per triangle.
+    #[default]
+    Binary,
+}
+
+/// Hold state information related to the storage of
+/// a watertight triangle mesh in an STL file.
+///
+/// STL is a soup of triangles, for 3D printing the mesh must be closed.
+/// [`Stl::write`] refuses a mesh with boundary edges, so grids whose seams
+/// repeat their first point must be joined with [`Stl::weld`] first.
+///
+/// Indices start at 0. STL has no units, see [`Stl::fit_to`].
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Stl {
+    /// All points of the mesh.
+    pub vertex_buffer: Vec<Vertex>,
+    /// Triangles, counter clockwise when seen from outside.
+    pub triangles: Vec<[usize; 3]>,
+}
+
+impl Stl {
+    /// Append the vertices of a woven grid, each quad split into two triangles.
+    pub fn push_grid(&mut self, grid: &Grid) {
+        let offset = self.vertex_buffer.len();
+        self.vertex_buffer.extend_from_slice(&grid.vertices);
+        self.triangles.extend(
+            grid.triangles()
+                .map(|triangle| triangle.map(|i| i + offset)),
+        );
+    }
+
+    /// Merges vertices which lie within `tolerance` of each other.
+    ///
+    /// Triangles collapsed by the merge are dropped.
+    pub fn weld(&mut self, tolerance: f32) {
+        let (welded, remap) = weld(&self.vertex_buffer, tolerance);
+        self.triangles = self
+            .triangles
+            .iter()
+            .map(|triangle| triangle.map(|i| remap[i]))
+            .filter(|[a, b, c]| a != b && b != c && c != a)
+            .collect();
+        self.vertex_buffer = welded;
+    }
+
+    /// Edges not shared by exactly two triangles, as sorted vertex pairs.
+    ///
+    /// Empty for a closed, manifold mesh.
+    #[must_use]
+    pub fn boundary_edges(&self) -> Vec<[usize; 2]> {
+        let mut counts: HashMap<[usize; 2], usize> = HashMap::default();
+        for [a, b, c] in &self.triangles {
+            for (p, q) in [(a, b), (b, c), (c, a)] {
+                *counts.entry([*p.min(q), *p.max(q)]).or_default() += 1;
+            }
+        }
+        let mut edges = counts
+            .into_iter()
+            .filter(|(_, count)| *count != 2)
+            .map(|(edge, _)| edge)
+            .collect::<Vec<_>>();
+        edges.sort_unstable();
+        edges
+    }
+
+    /// The smallest box holding every vertex, as its minimum and maximum corners.
+    #[must_use]
+    pub fn bounds(&self) -> Option<(Vec3, Vec3)> {
+        self.vertex_buffer
+            .iter()
+            .fold(None, |bounds, v| match bounds {
+                None => Some((v.0, v.0)),
+                Some((min, max)) => Some((min.min(v.0), max.max(v.0))),
+            })
+    }
+
+    /// Moves the mesh onto the origin, and scales its largest side to `size`.
+    ///
+    /// For printing, `size` is the target length in millimetres.
+    /// Returns the scale factor applied.
+    pub fn fit_to(&mut self, size: f32) -> f32 {
+        let Some((min, max)) = self.bounds() else {
+            return 1_f32;
+        };
+        let extent = (max - min).max_element();
+        let factor = if extent > 0_f32 { size / extent } else { 1_f32 };
+        for v in &mut self.vertex_buffer {
+            v.0 = (v.0 - min) * factor;
+        }
+        factor
+    }
+
+    // Only closed meshes can be printed.
+    fn validate(&self) -> Result<(), Error> {
+        let n = self.vertex_buffer.len();
+        if let Some(i) = self.triangles.iter().flatten().find(|i| **i >= n) {
+            return Err(Error::other(format!(
+                "Cannot write an STL file, index {i} is out of range for {n} vertices."
+            )));
+        }
+        let boundary = self.boundary_edges();
+        if let Some([a, b]) = boundary.first() {
+            let (Vertex(p), Vertex(q)) = (self.vertex_buffer[*a], self.vertex_buffer[*b]);
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "Cannot write an STL file, the mesh is not watertight: {} boundary edges, \
+                     such as {p} to {q}. Weld the seams, or thicken the surface.",
+                    boundary.len()
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    // The outward facing normal and corners of each triangle.
+    fn facets(&self) -> impl Iterator<Item = (Vec3, [Vec3; 3])> + '_ {
+        self.triangles.iter().map(|triangle| {
+            let [a, b, c] = triangle.map(|i| self.vertex_buffer[i].0);
+            ((b - a).cross(c - a).normalize_or_zero(), [a, b, c])
+        })
+    }
+
+    /// Writes the mesh as an STL file.
+    ///
+    /// # Errors
+    ///   When writing to a buffer fails, or the mesh has boundary edges.
+    pub fn write<W>(&self, format: StlFormat, out: &mut BufWriter<W>) -> Result<(), Error>
+    where
+        W: ?Sized + std::io::Write,
+    {
+        self.validate()?;
+        match format {
+            StlFormat::Ascii => {
+                writeln!(out, "solid hopf")?;
+                for (normal, corners) in self.facets() {
+                    writeln!(out, "facet normal {} {} {}", normal.x, normal.y, normal.z)?;
+                    writeln!(out, "outer loop")?;
+                    for Vec3 { x, y, z } in corners {
+                        writeln!(out, "vertex {x} {y} {z}")?;
+                    }
+                    writeln!(out, "endloop")?;
+                    writeln!(out, "endfacet")?;
+                }
+                writeln!(out, "endsolid hopf")?;
+            }
+            StlFormat::Binary => {
+                let mut header = [0_u8; 80];
+                header[..4].copy_from_slice(b"hopf");
+                out.write_all(&header)?;
+                let n = u32::try_from(self.triangles.len())
+                    .map_err(|_| Error::other("Cannot write more than 2^32 STL triangles."))?;
+                out.write_all(&n.to_le_bytes())?;
+                for (normal, corners) in self.facets() {
+                    for v in core::iter::once(normal).chain(corners) {
+                        for component in v.to_array() {
+                            out.write_all(&component.to_le_bytes())?;
+                        }
+                    }
+                    // Attribute byte count, unused.
+                    out.write_all(&0_u16.to_le_bytes())?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::tube;
+
+    // A closed ring of `n` points, the last repeating the first.
+    fn circle(n: u16) -> Vec<Vertex> {
+        (0..=n)
+            .map(|i| {
+                let theta = core::f32::consts::TAU * f32::from(i % n) / f32::from(n);
+                Vertex(Vec3::new(3_f32 * theta.cos(), 3_f32 * theta.sin(), 0_f32))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn torus_is_watertight_once_welded() {
+        let mut stl = Stl::default();
+        stl.push_grid(&tube(&circle(16), 1_f32, 8));
+        // The seams repeat their first ring, and first point.
+        assert!(!stl.boundary_edges().is_empty());
+
+        stl.weld(1e-4);
+        assert_eq!(stl.vertex_buffer.len(), 16 * 8);
+        assert!(stl.boundary_edges().is_empty());
+
+        let mut out = BufWriter::new(vec![]);
+        stl.write(StlFormat::Binary, &mut out).unwrap();
+        let bytes = out.into_inner().unwrap();
+        assert_eq!(bytes.len(), 84 + 50 * 16 * 8 * 2);
+        assert_eq!(u32::from_le_bytes(bytes[80..84].try_into().unwrap()), 256);
+
+        // Normals face away from the core of the tube.
+        for (normal, [a, b, c]) in stl.facets() {
+            let centre = (a + b + c) / 3_f32;
+            let core = Vec3::new(centre.x, centre.y, 0_f32).normalize() * 3_f32;
+            assert!(normal.dot(centre - core) > 0_f32);
+        }
+    }
+
+    #[test]
+    fn open_tube_is_refused() {
+        let path = [Vertex(Vec3::ZERO), Vertex(Vec3::Z), Vertex(2_f32 * Vec3::Z)];
+        let mut stl = Stl::default();
+        stl.push_grid(&tube(&path, 1_f32, 6));
+        stl.weld(1e-4);
+        // Two open rings of 6 edges.
+        assert_eq!(stl.boundary_edges().len(), 12);
+
+        let e = stl
+            .write(StlFormat::Ascii, &mut BufWriter::new(vec![]))
+            .unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::InvalidData);
+        assert!(e.to_string().contains("12 boundary edges"));
+    }
+
+    #[test]
+    fn thickened_surfaces_are_watertight() {
+        let mut sheet = Grid::new(5);
+        for z in 0..4_u8 {
+            let points = (0..5_u8)
+                .map(|x| {
+                    Vertex(Vec3::new(
+                        f32::from(x),
+                        0.1 * f32::from(x * z),
+                        f32::from(z),
+                    ))
+                })
+                .collect::<Vec<_>>();
+            sheet.push_loop(&points);
+        }
+        let path = [Vertex(Vec3::ZERO), Vertex(Vec3::Z), Vertex(2_f32 * Vec3::Z)];
+
+        for grid in [sheet, tube(&path, 1_f32, 6)] {
+            let mut stl = Stl::default();
+            for (layer, _) in grid.solidify(0.2).unwrap() {
+                stl.push_grid(&layer);
+            }
+            stl.weld(1e-4);
+            assert!(stl.boundary_edges().is_empty());
+
+            // Oriented outward, the signed volume is positive.
+            let volume = stl
+                .facets()
+                .map(|(_, [a, b, c])| a.dot(b.cross(c)) / 6_f32)
+                .sum::<f32>();
+            assert!(volume > 0_f32, "{volume}");
+            stl.write(StlFormat::Binary, &mut BufWriter::new(vec![]))
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn fit_to_millimetres() {
+        let mut stl = Stl::default();
+        stl.push_grid(&tube(&circle(16), 1_f32, 8));
+        stl.weld(1e-4);
+        let factor = stl.fit_to(80_f32);
+        assert!((factor - 10_f32).abs() < 1e-3);
+        let (min, max) = stl.bounds().unwrap();
+        assert!(min.abs_diff_eq(Vec3::ZERO, 1e-4));
+        assert!((max.max_element() - 80_f32).abs() < 1e-3);
+
+        let mut out = BufWriter::new(vec![]);
+        stl.write(StlFormat::Ascii, &mut out).unwrap();
+        let text = String::from_utf8(out.into_inner().unwrap()).unwrap();
+        assert!(text.starts_with("solid hopf\nfacet normal"));
+        assert_eq!(text.matches("endfacet").count(), 256);
+    }
+}
@@ -0,0 +1,491 @@
+use crate::Vertex;
+use std::io::BufWriter;
+use std::io::Error;
+use std::io::Write;
+
+use glam::Mat4;
+use glam::Vec2;
+use glam::Vec3;
+
+// Strokes are grouped by depth into this many bands, each drawn at one width.
+const DEPTH_BANDS: u8 = 8;
+
+// Crossings this close to the end of a segment are left to its neighbour.
+const END_TOLERANCE: f32 = 1e-6;
+
+/// How the camera maps points onto the figure.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SvgProjection {
+    /// Parallel projection, distant fibres keep their size.
+    Orthographic,
+    /// Pinhole projection.
+    Perspective {
+        /// Vertical field of view, in radians.
+        fov_y: f32,
+    },
+}
+
+/// A camera at `eye`, looking towards `target`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SvgCamera {
+    /// The position of the camera.
+    pub eye: Vec3,
+    /// The point at the centre of view.
+    pub target: Vec3,
+    /// The direction which is up in the figure.
+    pub up: Vec3,
+    /// Orthographic or perspective.
+    pub projection: SvgProjection,
+}
+
+impl Default for SvgCamera {
+    fn default() -> Self {
+        Self {
+            eye: Vec3::new(0_f32, 0_f32, 10_f32),
+            target: Vec3::ZERO,
+            up: Vec3::Y,
+            projection: SvgProjection::Orthographic,
+        }
+    }
+}
+
+impl SvgCamera {
+    /// A point on the image plane, with its distance in front of the camera.
+    ///
+    /// The image plane is y down, as in SVG. Its scale is arbitrary,
+    /// [`Svg::write`] fits the drawing to the figure.
+    /// `None` for a perspective camera, when the point is not in front of the eye.
+    #[must_use]
+    pub fn project(&self, p: Vec3) -> Option<(Vec2, f32)> {
+        let view = Mat4::look_at_rh(self.eye, self.target, self.up).transform_point3(p);
+        let depth = -view.z;
+        let xy = match self.projection {
+            SvgProjection::Orthographic => view.truncate(),
+            SvgProjection::Perspective { fov_y } => {
+                if depth <= 0_f32 {
+                    return None;
+                }
+                view.truncate() / (depth * (fov_y / 2_f32).tan())
+            }
+        };
+        Some((Vec2::new(xy.x, -xy.y), depth))
+    }
+}
+
+/// A polyline, such as a sampled fibre, drawn in a single colour.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SvgPolyline {
+    /// Points along the line.
+    pub points: Vec<Vertex>,
+    /// Linear RGB stroke colour, each component 0..=1.
+    pub color: [f32; 3],
+}
+
+/// Hold state information related to drawing polylines as an SVG figure.
+///
+/// Each polyline is projected by the camera, and written as `<path>` elements.
+/// Where two lines cross, the one behind is broken either side of the one in
+/// front. Paths are painted furthest first, and thin out with depth.
+///
+/// Sizes are in pixels, the drawing is scaled to fit within the margin.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Svg {
+    /// Width of the figure.
+    pub width: f32,
+    /// Height of the figure.
+    pub height: f32,
+    /// Empty space around the drawing.
+    pub margin: f32,
+    /// The viewpoint.
+    pub camera: SvgCamera,
+    /// Stroke width of the nearest lines.
+    pub stroke_width: f32,
+    /// Stroke width of the furthest lines, as a fraction of the nearest.
+    ///
+    /// One draws every line alike.
+    pub far_width: f32,
+    /// Clear space either side of a line passing in front.
+    ///
+    /// Negative draws every line unbroken.
+    pub gap: f32,
+    /// The polylines, in the order pushed.
+    pub polylines: Vec<SvgPolyline>,
+}
+
+impl Default for Svg {
+    fn default() -> Self {
+        Self {
+            width: 800_f32,
+            height: 800_f32,
+            margin: 20_f32,
+            camera: SvgCamera::default(),
+            stroke_width: 2_f32,
+            far_width: 0.5,
+            gap: 2_f32,
+            polylines: vec![],
+        }
+    }
+}
+
+// A projected step along a polyline, from point `k` to `k + 1`.
+struct Segment {
+    polyline: usize,
+    k: usize,
+    ends: [Vec2; 2],
+    depths: [f32; 2],
+}
+
+impl Segment {
+    fn at(&self, u: f32) -> Vec2 {
+        self.ends[0].lerp(self.ends[1], u)
+    }
+
+    fn depth(&self) -> f32 {
+        self.depth_at(0.5)
+    }
+
+    fn depth_at(&self, u: f32) -> f32 {
+        u.mul_add(self.depths[1] - self.depths[0], self.depths[0])
+    }
+
+    // Where the segments cross, as the fraction along each.
+    fn crossing(&self, other: &Self) -> Option<(f32, f32)> {
+        let [a, b] = self.ends;
+        let [c, d] = other.ends;
+        if a.min(b).cmpgt(c.max(d)).any() || c.min(d).cmpgt(a.max(b)).any() {
+            return None;
+        }
+        let (r, q) = (b - a, d - c);
+        let denom = r.perp_dot(q);
+        if denom.abs() <= f32::EPSILON {
+            return None;
+        }
+        let u = (c - a).perp_dot(q) / denom;
+        let v = (c - a).perp_dot(r) / denom;
+        let interior = END_TOLERANCE..(1_f32 - END_TOLERANCE);
+        (interior.contains(&u) && interior.contains(&v)).then_some((u, v))
+    }
+}
+
+// Visible segments of one polyline, joined into a path.
+struct Piece {
+    polyline: usize,
+    band: u8,
+    points: Vec<Vec2>,
+    depths: Vec<f32>,
+}
+
+impl Piece {
+    #[allow(clippy::cast_precision_loss)]
+    fn depth(&self) -> f32 {
+        self.depths.iter().sum::<f32>() / self.depths.len().max(1) as f32
+    }
+}
+
+// The parts of 0..=1 not covered by any gap.
+fn visible(mut gaps: Vec<[f32; 2]>) -> Vec<[f32; 2]> {
+    gaps.sort_by(|a, b| a[0].total_cmp(&b[0]));
+    let mut intervals = vec![];
+    let mut start = 0_f32;
+    for [from, to] in gaps {
+        if from > start {
+            intervals.push([start, from.min(1_f32)]);
+        }
+        start = start.max(to);
+    }
+    if start < 1_f32 {
+        intervals.push([start, 1_f32]);
+    }
+    intervals
+}
+
+// A colour component as a byte.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn byte(c: f32) -> u8 {
+    (c.clamp(0_f32, 1_f32) * 255_f32).round() as u8
+}
+
+impl Svg {
+    /// Appends a polyline, such as a fibre, drawn in `color`.
+    pub fn push_polyline(&mut self, line: &[Vertex], color: [f32; 3]) {
+        self.polylines.push(SvgPolyline {
+            points: line.to_vec(),
+            color,
+        });
+    }
+
+    // Every step between two projected points, in figure coordinates.
+    fn segments(&self) -> Vec<Segment> {
+        let projected = self
+            .polylines
+            .iter()
+            .map(|line| {
+                line.points
+                    .iter()
+                    .map(|v| self.camera.project(v.0))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        // Scale the drawing to fit within the margin, and centre it.
+        let (min, max) = projected.iter().flatten().flatten().fold(
+            (Vec2::INFINITY, Vec2::NEG_INFINITY),
+            |(min, max), (p, _)| (min.min(*p), max.max(*p)),
+        );
+        let space = Vec2::new(self.width, self.height) - 2_f32 * self.margin;
+        let scale = (space / (max - min).max(Vec2::splat(f32::EPSILON))).min_element();
+        let centre = (min + max) / 2_f32;
+        let origin = Vec2::new(self.width, self.height) / 2_f32;
+
+        let mut segments = vec![];
+        for (polyline, points) in projected.iter().enumerate() {
+            for (k, pair) in points.windows(2).enumerate() {
+                if let [Some((a, da)), Some((b, db))] = pair {
+                    segments.push(Segment {
+                        polyline,
+                        k,
+                        ends: [a, b].map(|p| (*p - centre) * scale + origin),
+                        depths: [*da, *db],
+                    });
+                }
+            }
+        }
+        segments
+    }
+
+    // Which band of depth, from 0 for the nearest.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn band(depth: f32, (near, far): (f32, f32)) -> u8 {
+        let t = if far > near {
+            (depth - near) / (far - near)
+        } else {
+            0_f32
+        };
+        ((t * f32::from(DEPTH_BANDS)) as u8).min(DEPTH_BANDS - 1)
+    }
+
+    fn band_width(&self, band: u8) -> f32 {
+        let t = f32::from(band) / f32::from(DEPTH_BANDS - 1);
+        self.stroke_width * (1_f32 - t + t * self.far_width)
+    }
+
+    // For each segment, the fractions hidden by segments crossing in front.
+    fn gaps(&self, segments: &[Segment], range: (f32, f32)) -> Vec<Vec<[f32; 2]>> {
+        let mut gaps = vec![vec![]; segments.len()];
+        if self.gap < 0_f32 {
+            return gaps;
+        }
+        for (i, s) in segments.iter().enumerate() {
+            for (j, t) in segments.iter().enumerate().skip(i + 1) {
+                let Some((u, v)) = s.crossing(t) else {
+                    continue;
+                };
+                let (back, at, front) = if s.depth_at(u) > t.depth_at(v) {
+                    (i, u, t)
+                } else {
+                    (j, v, s)
+                };
+                let length = segments[back].ends[0].distance(segments[back].ends[1]);
+                let clear = self.gap + self.band_width(Self::band(front.depth(), range)) / 2_f32;
+                let half = clear / length.max(f32::EPSILON);
+                gaps[back].push([at - half, at + half]);
+            }
+        }
+        gaps
+    }
+
+    // The visible parts of each polyline, as paths of a single width.
+    fn pieces(&self) -> Vec<Piece> {
+        let segments = self.segments();
+        let range = segments
+            .iter()
+            .flat_map(|s| s.depths)
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(near, far), d| {
+                (near.min(d), far.max(d))
+            });
+        let gaps = self.gaps(&segments, range);
+
+        let mut pieces: Vec<Piece> = vec![];
+        // The polyline and point where the last piece ends.
+        let mut end = None;
+        for (segment, gaps) in segments.iter().zip(gaps) {
+            let band = Self::band(segment.depth(), range);
+            let mut next_end = None;
+            for [u0, u1] in visible(gaps) {
+                let joins = u0 <= 0_f32
+                    && end == Some((segment.polyline, segment.k))
+                    && pieces.last().is_some_and(|piece| piece.band == band);
+                if !joins {
+                    pieces.push(Piece {
+                        polyline: segment.polyline,
+                        band,
+                        points: vec![segment.at(u0)],
+                        depths: vec![],
+                    });
+                }
+                if let Some(piece) = pieces.last_mut() {
+                    piece.points.push(segment.at(u1));
+                    piece.depths.push(segment.depth());
+                }
+                if u1 >= 1_f32 {
+                    next_end = Some((segment.polyline, segment.k + 1));
+                }
+            }
+            end = next_end;
+        }
+
+        // Painted furthest first.
+        pieces.sort_by(|a, b| b.depth().total_cmp(&a.depth()));
+        pieces
+    }
+
+    /// Writes the figure as an SVG file.
+    ///
+    /// # Errors
+    ///   When writing to a buffer fails, or the margin leaves no room to draw.
+    pub fn write<W>(&self, out: &mut BufWriter<W>) -> Result<(), Error>
+    where
+        W: ?Sized + std::io::Write,
+    {
+        if self.width <= 2_f32 * self.margin || self.height <= 2_f32 * self.margin {
+            return Err(Error::other(format!(
+                "Cannot write a {} x {} SVG figure with a margin of {}.",
+                self.width, self.height, self.margin
+            )));
+        }
+
+        writeln!(
+            out,
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>
+<svg
+   width="{w}px"
+   height="{h}px"
+   viewBox="0 0 {w} {h}"
+   version="1.1"
+   xmlns="http://www.w3.org/2000/svg">
+<g fill="none" stroke-linecap="round" stroke-linejoin="round">"#,
+            w = self.width,
+            h = self.height
+        )?;
+        for piece in self.pieces() {
+            let [r, g, b] = self.polylines[piece.polyline].color.map(byte);
+            write!(out, "<path d=\"M")?;
+            for (i, p) in piece.points.iter().enumerate() {
+                let command = if i == 1 { " L" } else { "" };
+                write!(out, "{command} {:.2},{:.2}", p.x, p.y)?;
+            }
+            writeln!(
+                out,
+                r##"" stroke="#{r:02x}{g:02x}{b:02x}" stroke-width="{:.2}" />"##,
+                self.band_width(piece.band)
+            )?;
+        }
+        writeln!(out, "</g>\n</svg>")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fibre::Fibre;
+    use crate::sp::SurfacePoint;
+
+    fn write(svg: &Svg) -> String {
+        let mut out = BufWriter::new(vec![]);
+        svg.write(&mut out).unwrap();
+        String::from_utf8(out.into_inner().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn cameras_project() {
+        let p = Vec3::new(1_f32, 2_f32, -5_f32);
+        let ortho = SvgCamera::default();
+        let (xy, depth) = ortho.project(p).unwrap();
+        // y runs down the figure.
+        assert!(xy.abs_diff_eq(Vec2::new(1_f32, -2_f32), 1e-5));
+        assert!((depth - 15_f32).abs() < 1e-5);
+
+        let perspective = SvgCamera {
+            projection: SvgProjection::Perspective {
+                fov_y: core::f32::consts::FRAC_PI_2,
+            },
+            ..SvgCamera::default()
+        };
+        let (near, _) = perspective.project(Vec3::new(1_f32, 0_f32, 5_f32)).unwrap();
+        let (far, _) = perspective
+            .project(Vec3::new(1_f32, 0_f32, -5_f32))
+            .unwrap();
+        assert!(near.abs_diff_eq(Vec2::new(0.2, 0_f32), 1e-5));
+        assert!(far.abs_diff_eq(Vec2::new(1_f32 / 15_f32, 0_f32), 1e-5));
+        assert!(
+            perspective
+                .project(Vec3::new(0_f32, 0_f32, 20_f32))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn lines_behind_are_broken() {
+        let mut svg = Svg::default();
+        // A red line in front, crossing over a blue line.
+        let front = [
+            Vec3::new(-1_f32, 0_f32, 1_f32),
+            Vec3::new(1_f32, 0_f32, 1_f32),
+        ];
+        let back = [
+            Vec3::new(0_f32, -1_f32, -1_f32),
+            Vec3::new(0_f32, 1_f32, -1_f32),
+        ];
+        svg.push_polyline(&front.map(Vertex), [1_f32, 0_f32, 0_f32]);
+        svg.push_polyline(&back.map(Vertex), [0_f32, 0_f32, 1_f32]);
+
+        let text = write(&svg);
+        assert!(text.starts_with("<?xml"));
+        assert!(text.contains(r#"viewBox="0 0 800 800""#));
+        let paths = text
+            .lines()
+            .filter(|l| l.starts_with("<path"))
+            .collect::<Vec<_>>();
+        assert_eq!(paths.len(), 3);
+        // The blue line, broken in two and thinner, is painted first.
+        assert!(paths[0].contains("#0000ff") && paths[1].contains("#0000ff"));
+        assert!(paths[0].contains(r#"stroke-width="1.00""#));
+        assert!(paths[2].contains("#ff0000"));
+        assert!(paths[2].contains(r#"d="M 20.00,400.00 L 780.00,400.00""#));
+        assert!(paths[2].contains(r#"stroke-width="2.00""#));
+
+        svg.gap = -1_f32;
+        assert_eq!(write(&svg).matches("<path").count(), 2);
+    }
+
+    #[test]
+    fn fibres_keep_their_colours() {
+        let alpha = 0_f32..=4.0 * core::f32::consts::PI;
+        let mut svg = Svg {
+            camera: SvgCamera {
+                eye: Vec3::new(4_f32, 6_f32, 8_f32),
+                projection: SvgProjection::Perspective { fov_y: 0.8 },
+                ..SvgCamera::default()
+            },
+            ..Svg::default()
+        };
+        for (i, lon) in [0_f32, 60_f32, 120_f32].into_iter().enumerate() {
+            let sp = SurfacePoint {
+                lat: 30_f32.to_radians(),
+                lon: lon.to_radians(),
+            };
+            let (points, _) = Fibre::new(sp, &alpha).build_uniform::<64>();
+            let mut color = [0_f32; 3];
+            color[i] = 1_f32;
+            svg.push_polyline(&points, color);
+        }
+
+        let text = write(&svg);
+        // Linked fibres pass behind each other, so each is broken somewhere.
+        for color in ["#ff0000", "#00ff00", "#0000ff"] {
+            assert!(text.matches(color).count() > 1, "{color}");
+        }
+        assert!(text.trim_end().ends_with("</svg>"));
+    }
+}
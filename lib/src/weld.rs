@@ -0,0 +1,99 @@
+//! Tolerance based merging of coincident vertices.
+
+use std::collections::HashMap;
+
+use crate::Vertex;
+
+/// Merges vertices which lie within `tolerance` of each other.
+///
+/// Returns the welded vertex buffer, and a remapping table where
+/// `remap[old_index]` is the index into the welded buffer.
+///
+/// The first vertex seen in a cluster is the one retained, so
+/// the relative order of the surviving vertices is preserved.
+///
+/// Points are binned into cubic cells of side `tolerance`, so only
+/// the 27 neighbouring cells need to be searched for each point.
+///
+/// # Panics
+///   When `tolerance` is not a positive number.
+#[must_use]
+pub fn weld(vertices: &[Vertex], tolerance: f32) -> (Vec<Vertex>, Vec<usize>) {
+    assert!(tolerance > 0_f32, "tolerance must be positive {tolerance}");
+
+    #[allow(clippy::cast_possible_truncation)]
+    let cell = |v: &Vertex| {
+        let c = (v.0 / tolerance).floor();
+        (c.x as i32, c.y as i32, c.z as i32)
+    };
+
+    let mut welded: Vec<Vertex> = Vec::with_capacity(vertices.len());
+    let mut remap = Vec::with_capacity(vertices.len());
+    // cell -> indices into welded.
+    let mut bins: HashMap<(i32, i32, i32), Vec<usize>> = HashMap::default();
+
+    for v in vertices {
+        let (cx, cy, cz) = cell(v);
+        let mut found = None;
+        'search: for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    if let Some(candidates) = bins.get(&(cx + dx, cy + dy, cz + dz)) {
+                        if let Some(&i) = candidates
+                            .iter()
+                            .find(|&&i| (welded[i] - *v).length() <= tolerance)
+                        {
+                            found = Some(i);
+                            break 'search;
+                        }
+                    }
+                }
+            }
+        }
+
+        let index = found.unwrap_or_else(|| {
+            let index = welded.len();
+            welded.push(*v);
+            bins.entry((cx, cy, cz)).or_default().push(index);
+            index
+        });
+        remap.push(index);
+    }
+
+    (welded, remap)
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+
+    use super::*;
+
+    #[test]
+    fn merges_within_tolerance() {
+        let vertices = [
+            Vertex(Vec3::new(0_f32, 0_f32, 0_f32)),
+            Vertex(Vec3::new(1_f32, 0_f32, 0_f32)),
+            // Differs from the first point by rounding only.
+            Vertex(Vec3::new(1e-7, 0_f32, -1e-7)),
+            Vertex(Vec3::new(1_f32, 1_f32, 0_f32)),
+        ];
+
+        let (welded, remap) = weld(&vertices, 1e-5);
+        assert_eq!(welded.len(), 3);
+        assert_eq!(remap, vec![0, 1, 0, 2]);
+    }
+
+    #[test]
+    fn straddles_cell_boundary() {
+        // Both points are close, but fall into different cells.
+        let vertices = [
+            Vertex(Vec3::new(0.999_99, 0_f32, 0_f32)),
+            Vertex(Vec3::new(1.000_01, 0_f32, 0_f32)),
+        ];
+
+        let (welded, remap) = weld(&vertices, 1e-3);
+        assert_eq!(welded.len(), 1);
+        assert_eq!(remap, vec![0, 0]);
+    }
+}
@@ -1,2 +1,2 @@
-rm *.obj
+rm *.obj *.mtl
 time cargo run --release > a.obj
@@ -9,13 +9,26 @@
 #![warn(missing_docs)]
 #![allow(clippy::many_single_char_names)]
 
+use std::fs::File;
 use std::io::{BufWriter, Error};
 
 use hopf::fibre::Fibre;
+use hopf::mesh::Grid;
 use hopf::mesh::weave;
 use hopf::obj::Obj;
+use hopf::obj::ObjMaterial;
 use hopf::sp::SurfacePoint;
 
+// Written alongside the OBJ, into the working directory.
+const MTL_FILE: &str = "shells.mtl";
+
+// Outer, middle and inner shell colours.
+const SHELLS: [(&str, [f32; 3]); 3] = [
+    ("outer", [0.85, 0.33, 0.1]),
+    ("middle", [0.93, 0.69, 0.13]),
+    ("inner", [0.3, 0.75, 0.93]),
+];
+
 fn main() -> Result<(), std::io::Error> {
     // TODO Take seed from stdIn.
 
@@ -62,52 +75,41 @@ fn main() -> Result<(), std::io::Error> {
     let mesh = weave(&start, &end, 27);
     meshes.push((0_f32..=4.0 * core::f32::consts::PI, mesh));
 
-    let mut obj = Obj::default();
+    let mut obj = Obj {
+        mtllib: Some(MTL_FILE.to_string()),
+        materials: SHELLS
+            .iter()
+            .map(|(name, color)| ObjMaterial {
+                name: (*name).to_string(),
+                color: *color,
+            })
+            .collect(),
+        ..Obj::default()
+    };
 
     for (i, (alpha_range, mesh)) in meshes.into_iter().enumerate() {
-        let mut seed_iter = mesh;
-
-        // Inspect don't consume.
-        let initial_sp = seed_iter
-            .next()
-            .expect("Must have more than one seed to make a mesh");
-
-        let fibre_last = Fibre::new(initial_sp, &alpha_range);
+        let mut grid = Grid::new(NUM_POINTS_PER_LOOP);
 
-        let (mut points_last, _alphas) = fibre_last.build_uniform::<NUM_POINTS_PER_LOOP>();
-
-        let mut quads = vec![];
-
-        for sp in seed_iter {
+        for sp in mesh {
             let fibre = Fibre::new(sp, &alpha_range);
 
             let (points, _alphas) = fibre.build_uniform::<NUM_POINTS_PER_LOOP>();
 
-            assert_eq!(points.len(), NUM_POINTS_PER_LOOP as usize);
-
-            //  0 - 3
-            //  | / |
-            //  |/  |
-            //  1 --2
-            //
-            // Given a quad ( points 0, 1, 2, 3 )
-            // form triangles (0,1,3) and (1,2,3)
-            // adding triangles will de-dupe points and compute normals.
-            for i in 1..NUM_POINTS_PER_LOOP as usize {
-                let i0 = obj.add_vertex(&points_last[i - 1]);
-                let i1 = obj.add_vertex(&points_last[i]);
-                let i2 = obj.add_vertex(&points[i]);
-                let i3 = obj.add_vertex(&points[i - 1]);
-                // Push a quad (Obj files default to anti-clockwise winding order).
-                quads.push([i0, i1, i2, i3]);
-            }
-
-            points_last = points;
+            grid.push_loop(&points);
         }
-        let name = format!("o object_{i}");
-        obj.push_quads(name, quads);
+
+        // Quads are indexed directly from the grid coordinates
+        // (Obj files default to anti-clockwise winding order).
+        let (name, _) = SHELLS[i];
+        obj.push_grid(name.to_string(), &grid);
+        obj.normals.extend(grid.normals());
+        obj.uvs.extend(grid.uvs());
+        obj.part_mut(name).material = Some(name.to_string());
     }
 
+    obj.write_mtl(&mut BufWriter::new(File::create(MTL_FILE)?))
+        .map_err(|_| Error::other("Error writing materials."))?;
+
     obj.write(&mut writer)
         .map_err(|_| Error::other("Error writing output."))?;
     Ok(())